@@ -0,0 +1,246 @@
+use crate::console::OpCode;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AssembleError {
+    UnknownOpCode { line: usize, op: String },
+    MissingOperand { line: usize },
+    InvalidOperand { line: usize, operand: String },
+    UnexpectedToken { line: usize, token: String },
+    UnknownLabel { line: usize, label: String },
+    DuplicateLabel { line: usize, label: String },
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssembleError::UnknownOpCode { line, op } => {
+                write!(f, "line {}: unknown op code {}", line, op)
+            }
+            AssembleError::MissingOperand { line } => write!(f, "line {}: missing operand", line),
+            AssembleError::InvalidOperand { line, operand } => {
+                write!(f, "line {}: invalid operand {}", line, operand)
+            }
+            AssembleError::UnexpectedToken { line, token } => {
+                write!(f, "line {}: unexpected token {}", line, token)
+            }
+            AssembleError::UnknownLabel { line, label } => {
+                write!(f, "line {}: unknown label {}", line, label)
+            }
+            AssembleError::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label {} is already defined", line, label)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+enum Operand<'a> {
+    Immediate(i32),
+    Label(&'a str),
+}
+
+struct Statement<'a> {
+    line: usize,
+    op: &'a str,
+    operand: Operand<'a>,
+}
+
+fn is_label(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_operand(line: usize, token: &str) -> Result<Operand<'_>, AssembleError> {
+    if is_label(token) {
+        return Ok(Operand::Label(token));
+    }
+    token
+        .parse()
+        .map(Operand::Immediate)
+        .map_err(|_| AssembleError::InvalidOperand {
+            line,
+            operand: token.to_owned(),
+        })
+}
+
+pub fn assemble(source: &str) -> Result<Vec<OpCode>, AssembleError> {
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut statements: Vec<Statement> = Vec::new();
+
+    for (i, raw) in source.lines().enumerate() {
+        let line = i + 1;
+        let mut text = raw.split('#').next().unwrap().trim();
+
+        while let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                return Err(AssembleError::InvalidOperand {
+                    line,
+                    operand: label.to_owned(),
+                });
+            }
+            if labels.insert(label, statements.len()).is_some() {
+                return Err(AssembleError::DuplicateLabel {
+                    line,
+                    label: label.to_owned(),
+                });
+            }
+            text = rest.trim();
+        }
+
+        let mut tokens = text.split_whitespace();
+        let op = match tokens.next() {
+            Some(op) => op,
+            None => continue,
+        };
+        let operand = tokens
+            .next()
+            .ok_or(AssembleError::MissingOperand { line })
+            .and_then(|t| parse_operand(line, t))?;
+        if let Some(token) = tokens.next() {
+            return Err(AssembleError::UnexpectedToken {
+                line,
+                token: token.to_owned(),
+            });
+        }
+
+        statements.push(Statement { line, op, operand });
+    }
+
+    statements
+        .iter()
+        .enumerate()
+        .map(|(address, s)| {
+            let value = match s.operand {
+                Operand::Immediate(v) => v,
+                Operand::Label(label) if s.op != "acc" => labels
+                    .get(label)
+                    .map(|target| *target as i32 - address as i32)
+                    .ok_or_else(|| AssembleError::UnknownLabel {
+                        line: s.line,
+                        label: label.to_owned(),
+                    })?,
+                Operand::Label(label) => {
+                    return Err(AssembleError::InvalidOperand {
+                        line: s.line,
+                        operand: label.to_owned(),
+                    })
+                }
+            };
+
+            match s.op {
+                "nop" => Ok(OpCode::Nop(value)),
+                "acc" => Ok(OpCode::Acc(value)),
+                "jmp" => Ok(OpCode::Jmp(value)),
+                _ => Err(AssembleError::UnknownOpCode {
+                    line: s.line,
+                    op: s.op.to_owned(),
+                }),
+            }
+        })
+        .collect()
+}
+
+pub fn disassemble(program: &[OpCode]) -> String {
+    program
+        .iter()
+        .enumerate()
+        .map(|(address, op_code)| match op_code {
+            OpCode::Acc(_) => format!("{} # {}\n", op_code, address),
+            OpCode::Nop(v) | OpCode::Jmp(v) => format!(
+                "{} # {} -> {}\n",
+                op_code,
+                address,
+                address as i64 + *v as i64
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, disassemble, AssembleError};
+    use crate::console::OpCode;
+
+    #[test]
+    fn test_assemble_plain() {
+        let program = assemble("nop +0\nacc 1\njmp -1").unwrap();
+        assert_eq!(
+            program,
+            vec![OpCode::Nop(0), OpCode::Acc(1), OpCode::Jmp(-1)]
+        );
+    }
+
+    #[test]
+    fn test_assemble_labels_and_comments() {
+        let source = "
+            # count down
+            start: acc +3
+            loop:
+                acc -1 # decrement
+                jmp end
+                jmp loop
+            end: nop start";
+        let program = assemble(source).unwrap();
+        assert_eq!(
+            program,
+            vec![
+                OpCode::Acc(3),
+                OpCode::Acc(-1),
+                OpCode::Jmp(2),
+                OpCode::Jmp(-2),
+                OpCode::Nop(-4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        assert_eq!(
+            assemble("nop +0\nmul +2"),
+            Err(AssembleError::UnknownOpCode {
+                line: 2,
+                op: "mul".to_owned()
+            })
+        );
+        assert_eq!(
+            assemble("jmp"),
+            Err(AssembleError::MissingOperand { line: 1 })
+        );
+        assert_eq!(
+            assemble("jmp nowhere"),
+            Err(AssembleError::UnknownLabel {
+                line: 1,
+                label: "nowhere".to_owned()
+            })
+        );
+        assert_eq!(
+            assemble("a: nop +0\na: nop +0"),
+            Err(AssembleError::DuplicateLabel {
+                line: 2,
+                label: "a".to_owned()
+            })
+        );
+        assert_eq!(
+            assemble("acc +1 +2"),
+            Err(AssembleError::UnexpectedToken {
+                line: 1,
+                token: "+2".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn test_disassemble_round_trip() {
+        let program = vec![OpCode::Nop(0), OpCode::Acc(-7), OpCode::Jmp(-2)];
+        let text = disassemble(&program);
+        assert_eq!(text, "nop +0 # 0 -> 0\nacc -7 # 1\njmp -2 # 2 -> 0\n");
+        assert_eq!(assemble(&text).unwrap(), program);
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Closures fill `buffer` on demand while precomputed tables hand out their own lists.
+pub trait Neighbourhood {
+    fn neighbours<'a>(&'a self, cell: usize, buffer: &'a mut Vec<usize>) -> &'a [usize];
+}
+
+impl<F: Fn(usize, &mut Vec<usize>)> Neighbourhood for F {
+    fn neighbours<'a>(&'a self, cell: usize, buffer: &'a mut Vec<usize>) -> &'a [usize] {
+        buffer.clear();
+        self(cell, buffer);
+        buffer
+    }
+}
+
+impl Neighbourhood for Vec<Vec<usize>> {
+    fn neighbours<'a>(&'a self, cell: usize, _buffer: &'a mut Vec<usize>) -> &'a [usize] {
+        &self[cell]
+    }
+}
+
+pub struct Neighbours<'a, C> {
+    cells: &'a [C],
+    indices: &'a [usize],
+}
+
+impl<'a, C> Neighbours<'a, C> {
+    pub fn iter(&self) -> impl Iterator<Item = &'a C> + '_ {
+        self.indices.iter().map(move |i| &self.cells[*i])
+    }
+
+    pub fn count(&self, predicate: impl Fn(&C) -> bool) -> usize {
+        self.iter().filter(|c| predicate(c)).count()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+pub struct Automaton<C, N, R> {
+    cells: Vec<C>,
+    next: Vec<C>,
+    neighbourhood: N,
+    rule: R,
+    generation: usize,
+    buffer: Vec<usize>,
+}
+
+impl<C, N, R> Automaton<C, N, R>
+where
+    C: Clone + PartialEq,
+    N: Neighbourhood,
+    R: Fn(&C, Neighbours<C>) -> C,
+{
+    pub fn new(cells: Vec<C>, neighbourhood: N, rule: R) -> Self {
+        Self {
+            next: cells.clone(),
+            cells,
+            neighbourhood,
+            rule,
+            generation: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn cells(&self) -> &[C] {
+        &self.cells
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn step(&mut self) -> bool {
+        let mut changed = false;
+        for i in 0..self.cells.len() {
+            let neighbours = Neighbours {
+                cells: &self.cells,
+                indices: self.neighbourhood.neighbours(i, &mut self.buffer),
+            };
+            let state = (self.rule)(&self.cells[i], neighbours);
+            changed |= state != self.cells[i];
+            self.next[i] = state;
+        }
+        std::mem::swap(&mut self.cells, &mut self.next);
+        self.generation += 1;
+
+        changed
+    }
+
+    pub fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    pub fn run_until_stable(&mut self) -> usize {
+        while self.step() {}
+        self.generation
+    }
+
+    pub fn find_cycle(&mut self, max_steps: usize) -> Option<Cycle>
+    where
+        C: Hash + Eq,
+    {
+        let mut seen: HashMap<Vec<C>, usize> = HashMap::new();
+        for _ in 0..=max_steps {
+            if let Some(start) = seen.insert(self.cells.clone(), self.generation) {
+                return Some(Cycle {
+                    start,
+                    period: self.generation - start,
+                });
+            }
+            self.step();
+        }
+
+        None
+    }
+}
+
+pub fn moore(shape: &[usize]) -> impl Fn(usize, &mut Vec<usize>) {
+    let shape = shape.to_vec();
+    let mut strides = vec![1usize; shape.len()];
+    for d in (0..shape.len().saturating_sub(1)).rev() {
+        strides[d] = strides[d + 1] * shape[d + 1];
+    }
+
+    move |cell, out| {
+        let coord: Vec<usize> = strides
+            .iter()
+            .zip(shape.iter())
+            .map(|(stride, size)| cell / stride % size)
+            .collect();
+        let mut offset = vec![-1i64; shape.len()];
+        'outer: loop {
+            if offset.iter().any(|o| *o != 0) {
+                let neighbour = coord
+                    .iter()
+                    .zip(offset.iter())
+                    .zip(shape.iter())
+                    .map(|((c, o), size)| {
+                        Some(*c as i64 + o).filter(|n| (0..*size as i64).contains(n))
+                    })
+                    .collect::<Option<Vec<i64>>>();
+                if let Some(n) = neighbour {
+                    out.push(
+                        n.iter()
+                            .zip(strides.iter())
+                            .map(|(n, s)| *n as usize * s)
+                            .sum(),
+                    );
+                }
+            }
+
+            for o in offset.iter_mut() {
+                if *o < 1 {
+                    *o += 1;
+                    continue 'outer;
+                }
+                *o = -1;
+            }
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{moore, Automaton, Cycle, Neighbourhood};
+
+    #[test]
+    fn test_moore_neighbourhood() {
+        let grid = moore(&[3, 3]);
+        let mut buffer = Vec::new();
+        let mut out = grid.neighbours(4, &mut buffer).to_vec();
+        out.sort_unstable();
+        assert_eq!(out, vec![0, 1, 2, 3, 5, 6, 7, 8]);
+
+        let mut out = grid.neighbours(0, &mut buffer).to_vec();
+        out.sort_unstable();
+        assert_eq!(out, vec![1, 3, 4]);
+
+        assert_eq!(moore(&[3, 3, 3]).neighbours(13, &mut buffer).len(), 26);
+    }
+
+    #[test]
+    fn test_blinker() {
+        let mut cells = vec![false; 25];
+        cells[11] = true;
+        cells[12] = true;
+        cells[13] = true;
+        let mut automaton = Automaton::new(cells.clone(), moore(&[5, 5]), |alive: &bool, n| {
+            let count = n.count(|c| *c);
+            count == 3 || (*alive && count == 2)
+        });
+        automaton.step();
+        assert!(automaton.cells()[7] && automaton.cells()[12] && automaton.cells()[17]);
+        assert!(!automaton.cells()[11]);
+        automaton.run(3);
+        assert_eq!(automaton.generation(), 4);
+        assert_eq!(automaton.cells(), &cells[..]);
+        assert_eq!(
+            automaton.find_cycle(10),
+            Some(Cycle {
+                start: 4,
+                period: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_precomputed_neighbourhood_is_borrowed() {
+        let table: Vec<Vec<usize>> = vec![vec![1, 2], vec![0], vec![0]];
+        let mut buffer = Vec::new();
+        let neighbours = table.neighbours(0, &mut buffer);
+        assert_eq!(neighbours, &[1, 2]);
+        assert!(std::ptr::eq(neighbours, &table[0][..]));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_run_until_stable() {
+        let neighbourhood: Vec<Vec<usize>> = vec![vec![1], vec![0, 2], vec![1]];
+        let mut automaton = Automaton::new(vec![1, 0, 0], neighbourhood, |c: &u32, n| {
+            *c.max(n.iter().max().unwrap())
+        });
+        assert_eq!(automaton.run_until_stable(), 3);
+        assert_eq!(automaton.cells(), &[1, 1, 1]);
+    }
+}
@@ -0,0 +1,324 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpCode {
+    Nop(i32),
+    Acc(i32),
+    Jmp(i32),
+}
+
+impl fmt::Display for OpCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OpCode::Nop(v) => write!(f, "nop {:+}", v),
+            OpCode::Acc(v) => write!(f, "acc {:+}", v),
+            OpCode::Jmp(v) => write!(f, "jmp {:+}", v),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct State {
+    pub pc: usize,
+    pub acc: i64,
+    pub steps: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitReason {
+    Terminated,
+    InfiniteLoop,
+    OutOfBounds(i64),
+    StepLimit,
+}
+
+pub trait Instruction {
+    // Applies the instruction to the state and returns the offset to move the program counter by.
+    fn execute(&self, state: &mut State) -> i64;
+}
+
+impl OpCode {
+    pub fn flipped(&self) -> Option<OpCode> {
+        match self {
+            OpCode::Nop(v) => Some(OpCode::Jmp(*v)),
+            OpCode::Jmp(v) => Some(OpCode::Nop(*v)),
+            OpCode::Acc(_) => None,
+        }
+    }
+
+    pub fn successor(&self, pc: usize) -> i64 {
+        match self {
+            OpCode::Jmp(v) => pc as i64 + *v as i64,
+            _ => pc as i64 + 1,
+        }
+    }
+}
+
+impl Instruction for OpCode {
+    fn execute(&self, state: &mut State) -> i64 {
+        match self {
+            OpCode::Nop(_) => 1,
+            OpCode::Acc(v) => {
+                state.acc += *v as i64;
+                1
+            }
+            OpCode::Jmp(v) => *v as i64,
+        }
+    }
+}
+
+pub struct Console<'a, I: Instruction = OpCode> {
+    program: &'a [I],
+    state: State,
+    visited: Vec<bool>,
+    step_limit: Option<usize>,
+    exit: Option<ExitReason>,
+}
+
+impl<'a, I: Instruction> Console<'a, I> {
+    pub fn new(program: &'a [I]) -> Self {
+        Self {
+            program,
+            state: State::default(),
+            visited: vec![false; program.len()],
+            step_limit: None,
+            exit: None,
+        }
+    }
+
+    pub fn with_step_limit(mut self, limit: usize) -> Self {
+        self.step_limit = Some(limit);
+        self
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    // Once the console has halted, every further step reports the same exit reason.
+    pub fn step(&mut self) -> Option<ExitReason> {
+        if self.exit.is_none() {
+            self.exit = self.advance();
+        }
+        self.exit
+    }
+
+    fn advance(&mut self) -> Option<ExitReason> {
+        let pc = self.state.pc;
+        if pc == self.program.len() {
+            return Some(ExitReason::Terminated);
+        }
+        if self.visited[pc] {
+            return Some(ExitReason::InfiniteLoop);
+        }
+        if self.step_limit.is_some_and(|l| self.state.steps >= l) {
+            return Some(ExitReason::StepLimit);
+        }
+
+        self.visited[pc] = true;
+        let offset = self.program[pc].execute(&mut self.state);
+        self.state.steps += 1;
+
+        let target = pc as i64 + offset;
+        if target < 0 || target > self.program.len() as i64 {
+            return Some(ExitReason::OutOfBounds(target));
+        }
+        self.state.pc = target as usize;
+
+        None
+    }
+
+    pub fn run(&mut self) -> ExitReason {
+        loop {
+            if let Some(reason) = self.step() {
+                return reason;
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Repair {
+    pub index: usize,
+    pub program: Vec<OpCode>,
+    pub acc: i64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RepairError {
+    AlreadyTerminates,
+    NoFix,
+    Ambiguous(Vec<usize>),
+}
+
+fn reaches_termination(program: &[OpCode]) -> Vec<bool> {
+    let n = program.len();
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n + 1];
+    for (pc, op_code) in program.iter().enumerate() {
+        let target = op_code.successor(pc);
+        if (0..=n as i64).contains(&target) {
+            predecessors[target as usize].push(pc);
+        }
+    }
+
+    let mut terminates = vec![false; n + 1];
+    terminates[n] = true;
+    let mut to_visit = vec![n];
+    while let Some(pc) = to_visit.pop() {
+        for p in predecessors[pc].iter() {
+            if !terminates[*p] {
+                terminates[*p] = true;
+                to_visit.push(*p);
+            }
+        }
+    }
+
+    terminates
+}
+
+pub fn repair(program: &[OpCode]) -> Result<Repair, RepairError> {
+    let terminates = reaches_termination(program);
+    if terminates[0] {
+        return Err(RepairError::AlreadyTerminates);
+    }
+
+    let mut visited = vec![false; program.len()];
+    let mut candidates = Vec::new();
+    let mut pc = 0i64;
+    while (0..program.len() as i64).contains(&pc) && !visited[pc as usize] {
+        let current = pc as usize;
+        visited[current] = true;
+        if let Some(flipped) = program[current].flipped() {
+            let target = flipped.successor(current);
+            if (0..=program.len() as i64).contains(&target) && terminates[target as usize] {
+                candidates.push(current);
+            }
+        }
+        pc = program[current].successor(current);
+    }
+
+    match candidates.as_slice() {
+        [] => Err(RepairError::NoFix),
+        [index] => {
+            let mut fixed = program.to_vec();
+            fixed[*index] = fixed[*index].flipped().unwrap();
+            let mut console = Console::new(&fixed);
+            console.run();
+            let acc = console.state().acc;
+            Ok(Repair {
+                index: *index,
+                program: fixed,
+                acc,
+            })
+        }
+        _ => Err(RepairError::Ambiguous(candidates)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{repair, Console, ExitReason, Instruction, OpCode, RepairError, State};
+
+    #[test]
+    fn test_terminates() {
+        let program = [OpCode::Acc(3), OpCode::Nop(-1), OpCode::Acc(-1)];
+        let mut console = Console::new(&program);
+        assert_eq!(console.run(), ExitReason::Terminated);
+        assert_eq!(
+            *console.state(),
+            State {
+                pc: 3,
+                acc: 2,
+                steps: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_infinite_loop() {
+        let program = [OpCode::Acc(1), OpCode::Jmp(-1)];
+        let mut console = Console::new(&program);
+        assert_eq!(console.run(), ExitReason::InfiniteLoop);
+        assert_eq!(console.state().pc, 0);
+        assert_eq!(console.state().acc, 1);
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let program = [OpCode::Nop(0), OpCode::Jmp(-5)];
+        let mut console = Console::new(&program);
+        assert_eq!(console.run(), ExitReason::OutOfBounds(-4));
+        assert_eq!(console.state().pc, 1);
+        assert_eq!(console.run(), ExitReason::OutOfBounds(-4));
+        assert_eq!(console.step(), Some(ExitReason::OutOfBounds(-4)));
+        assert_eq!(console.state().steps, 2);
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let program = [OpCode::Acc(1), OpCode::Acc(1), OpCode::Acc(1)];
+        let mut console = Console::new(&program).with_step_limit(2);
+        assert_eq!(console.run(), ExitReason::StepLimit);
+        assert_eq!(console.state().acc, 2);
+    }
+
+    enum Extended {
+        Base(OpCode),
+        Mul(i64),
+    }
+
+    impl Instruction for Extended {
+        fn execute(&self, state: &mut State) -> i64 {
+            match self {
+                Extended::Base(op) => op.execute(state),
+                Extended::Mul(v) => {
+                    state.acc *= v;
+                    1
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_extended_instruction_set() {
+        let program = [Extended::Base(OpCode::Acc(3)), Extended::Mul(4)];
+        let mut console = Console::new(&program);
+        assert_eq!(console.run(), ExitReason::Terminated);
+        assert_eq!(console.state().acc, 12);
+    }
+
+    #[test]
+    fn test_repair_given() {
+        let program = [
+            OpCode::Nop(0),
+            OpCode::Acc(1),
+            OpCode::Jmp(4),
+            OpCode::Acc(3),
+            OpCode::Jmp(-3),
+            OpCode::Acc(-99),
+            OpCode::Acc(1),
+            OpCode::Jmp(-4),
+            OpCode::Acc(6),
+        ];
+        let res = repair(&program).unwrap();
+        assert_eq!(res.index, 7);
+        assert_eq!(res.program[7], OpCode::Nop(-4));
+        assert_eq!(res.acc, 8);
+    }
+
+    #[test]
+    fn test_repair_errors() {
+        assert_eq!(
+            repair(&[OpCode::Acc(1)]),
+            Err(RepairError::AlreadyTerminates)
+        );
+        assert_eq!(
+            repair(&[OpCode::Acc(1), OpCode::Jmp(-1), OpCode::Jmp(5)]),
+            Err(RepairError::NoFix)
+        );
+        assert_eq!(
+            repair(&[OpCode::Nop(2), OpCode::Jmp(0)]),
+            Err(RepairError::Ambiguous(vec![0, 1]))
+        );
+    }
+}
@@ -1,52 +1,291 @@
+use num_bigint::{BigUint, RandBigInt};
+use rand::Rng;
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChainError {
+    Gap { from: i32, to: i32 },
+}
+
+pub struct AdapterChain {
+    adapters: Vec<i32>,
+    min_step: i32,
+    max_step: i32,
+    device_offset: i32,
+}
+
+impl AdapterChain {
+    pub fn new(adapters: &[i32]) -> Self {
+        let mut adapters = adapters.to_vec();
+        adapters.sort_unstable();
+        Self {
+            adapters,
+            min_step: 1,
+            max_step: 3,
+            device_offset: 3,
+        }
+    }
+
+    pub fn with_steps(mut self, min_step: i32, max_step: i32) -> Self {
+        self.min_step = min_step;
+        self.max_step = max_step;
+        self
+    }
+
+    pub fn with_device_offset(mut self, device_offset: i32) -> Self {
+        self.device_offset = device_offset;
+        self
+    }
+
+    pub fn device(&self) -> i32 {
+        self.adapters.last().copied().unwrap_or(0) + self.device_offset
+    }
+
+    fn fits(&self, from: i32, to: i32) -> bool {
+        (self.min_step..=self.max_step).contains(&(to - from))
+    }
+
+    fn joltages(&self) -> Vec<i32> {
+        std::iter::once(0)
+            .chain(self.adapters.iter().copied())
+            .chain(std::iter::once(self.device()))
+            .collect()
+    }
+
+    pub fn difference_histogram(&self) -> Result<BTreeMap<i32, usize>, ChainError> {
+        let mut histogram = BTreeMap::new();
+        for pair in self.joltages().windows(2) {
+            if !self.fits(pair[0], pair[1]) {
+                return Err(ChainError::Gap {
+                    from: pair[0],
+                    to: pair[1],
+                });
+            }
+            *histogram.entry(pair[1] - pair[0]).or_insert(0) += 1;
+        }
+
+        Ok(histogram)
+    }
+
+    fn successors<'a>(&'a self, joltages: &'a [i32], i: usize) -> impl Iterator<Item = usize> + 'a {
+        ((i + 1)..joltages.len())
+            .take_while(move |j| joltages[*j] - joltages[i] <= self.max_step)
+            .filter(move |j| self.fits(joltages[i], joltages[*j]))
+    }
+
+    fn ways_from_outlet(&self, joltages: &[i32]) -> Vec<BigUint> {
+        let mut paths: Vec<BigUint> = vec![BigUint::from(0u32); joltages.len()];
+        paths[0] = BigUint::from(1u32);
+
+        for i in 0..joltages.len() {
+            let current = paths[i].clone();
+            for j in self.successors(joltages, i) {
+                paths[j] += &current;
+            }
+        }
+
+        paths
+    }
+
+    fn ways_to_device(&self, joltages: &[i32]) -> Vec<BigUint> {
+        let mut paths: Vec<BigUint> = vec![BigUint::from(0u32); joltages.len()];
+        paths[joltages.len() - 1] = BigUint::from(1u32);
+
+        for i in (0..joltages.len() - 1).rev() {
+            paths[i] = self.successors(joltages, i).map(|j| &paths[j]).sum();
+        }
+
+        paths
+    }
+
+    pub fn arrangements(&self) -> BigUint {
+        self.ways_from_outlet(&self.joltages()).pop().unwrap()
+    }
+
+    pub fn iter_arrangements(&self) -> Arrangements<'_> {
+        let joltages = self.joltages();
+        let completable: Vec<bool> = self
+            .ways_to_device(&joltages)
+            .into_iter()
+            .map(|w| w != BigUint::from(0u32))
+            .collect();
+        let stack = if completable[0] {
+            vec![vec![0]]
+        } else {
+            vec![]
+        };
+
+        Arrangements {
+            chain: self,
+            joltages,
+            completable,
+            stack,
+        }
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Option<Vec<i32>> {
+        let joltages = self.joltages();
+        let ways = self.ways_to_device(&joltages);
+        if ways[0] == BigUint::from(0u32) {
+            return None;
+        }
+
+        let mut arrangement = Vec::new();
+        let mut current = 0;
+        while current != joltages.len() - 1 {
+            let mut pick = rng.gen_biguint_below(&ways[current]);
+            current = self
+                .successors(&joltages, current)
+                .find(|j| {
+                    if pick < ways[*j] {
+                        true
+                    } else {
+                        pick -= &ways[*j];
+                        false
+                    }
+                })
+                .unwrap();
+            arrangement.push(joltages[current]);
+        }
+        arrangement.pop();
+
+        Some(arrangement)
+    }
+
+    fn extreme_arrangement(&self, longest: bool) -> Option<Vec<i32>> {
+        let joltages = self.joltages();
+        let mut best: Vec<Option<(usize, usize)>> = vec![None; joltages.len()];
+        best[0] = Some((0, 0));
+
+        for i in 0..joltages.len() {
+            if let Some((length, _)) = best[i] {
+                for j in self.successors(&joltages, i) {
+                    let better = match best[j] {
+                        None => true,
+                        Some((l, _)) if longest => length + 1 > l,
+                        Some((l, _)) => length + 1 < l,
+                    };
+                    if better {
+                        best[j] = Some((length + 1, i));
+                    }
+                }
+            }
+        }
+
+        let mut current = joltages.len() - 1;
+        best[current]?;
+        let mut arrangement = Vec::new();
+        while current != 0 {
+            current = best[current].unwrap().1;
+            arrangement.push(joltages[current]);
+        }
+        arrangement.pop();
+        arrangement.reverse();
+
+        Some(arrangement)
+    }
+
+    pub fn shortest_arrangement(&self) -> Option<Vec<i32>> {
+        self.extreme_arrangement(false)
+    }
+
+    pub fn longest_arrangement(&self) -> Option<Vec<i32>> {
+        self.extreme_arrangement(true)
+    }
+
+    pub fn mandatory_adapters(&self) -> Vec<i32> {
+        let joltages = self.joltages();
+        let from_outlet = self.ways_from_outlet(&joltages);
+        let to_device = self.ways_to_device(&joltages);
+        let total = &to_device[0];
+        if *total == BigUint::from(0u32) {
+            return Vec::new();
+        }
+
+        (1..joltages.len() - 1)
+            .filter(|i| &(&from_outlet[*i] * &to_device[*i]) == total)
+            .map(|i| joltages[i])
+            .collect()
+    }
+
+    pub fn is_completable(&self) -> bool {
+        self.arrangements() != BigUint::from(0u32)
+    }
+}
+
+pub struct Arrangements<'a> {
+    chain: &'a AdapterChain,
+    joltages: Vec<i32>,
+    completable: Vec<bool>,
+    stack: Vec<Vec<usize>>,
+}
+
+impl<'a> Iterator for Arrangements<'a> {
+    type Item = Vec<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let device = self.joltages.len() - 1;
+        while let Some(path) = self.stack.pop() {
+            let last = *path.last().unwrap();
+            if last == device {
+                return Some(
+                    path[1..path.len() - 1]
+                        .iter()
+                        .map(|i| self.joltages[*i])
+                        .collect(),
+                );
+            }
+
+            let next: Vec<usize> = self
+                .chain
+                .successors(&self.joltages, last)
+                .filter(|j| self.completable[*j])
+                .collect();
+            for j in next.into_iter().rev() {
+                let mut extended = path.clone();
+                extended.push(j);
+                self.stack.push(extended);
+            }
+        }
+
+        None
+    }
+}
 
 #[aoc_generator(day10)]
-fn input_generator(input: &str) -> Vec<i32> {
-    let mut numbers: Vec<i32> = input
+fn input_generator(input: &str) -> AdapterChain {
+    let numbers: Vec<i32> = input
         .lines()
         .map(str::trim)
         .map(str::parse)
         .flatten()
         .collect();
-    numbers.sort_unstable();
-    numbers.insert(0, 0);
-    numbers
+    AdapterChain::new(&numbers)
 }
 
 #[aoc(day10, part1, window)]
-fn day10_part1(input: &Vec<i32>) -> u32 {
-    let mut frequency = vec![0, 0, 1];
-    input
-        .windows(2)
-        .map(|s| s[1] - s[0])
-        .for_each(|d| frequency[(d - 1) as usize] += 1);
-
-    frequency[0] * frequency[2]
+fn day10_part1(input: &AdapterChain) -> usize {
+    let histogram = input
+        .difference_histogram()
+        .unwrap_or_else(|e| panic!("Adapters can not be chained: {:?}", e));
+
+    histogram.get(&1).unwrap_or(&0) * histogram.get(&3).unwrap_or(&0)
 }
 
 #[aoc(day10, part2)]
-fn day10_part2(input: &Vec<i32>) -> u64 {
-    let mut paths: BTreeMap<i32, u64> = BTreeMap::new();
-    paths.insert(0, 1);
-
-    for i in input.iter().enumerate() {
-        let c = i.0;
-        let range = ((c as i64 - 3).max(0) as usize)..c;
-        let past_three: u64 = input[range]
-            .iter()
-            .filter(|p| i.1 - *p <= 3)
-            .map(|k| paths.get(k).unwrap())
-            .sum();
-
-        paths.insert(*i.1, past_three.max(1));
-    }
-
-    *paths.get(input.last().unwrap()).unwrap()
+fn day10_part2(input: &AdapterChain) -> u64 {
+    u64::try_from(input.arrangements()).expect("Arrangement count does not fit in u64")
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{day10_part1, day10_part2, input_generator};
+    use super::{day10_part1, day10_part2, input_generator, AdapterChain, ChainError};
+    use num_bigint::BigUint;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::{BTreeMap, HashSet};
+    use std::convert::TryFrom;
 
     const INPUT1: &'static str = "16
     10
@@ -118,4 +357,98 @@ mod tests {
         let res = day10_part2(&numbers);
         assert_eq!(19208, res);
     }
+
+    #[test]
+    fn test_histogram() {
+        let chain = AdapterChain::new(&[1, 4, 5, 7]);
+        let mut expected = BTreeMap::new();
+        expected.insert(1, 2);
+        expected.insert(2, 1);
+        expected.insert(3, 2);
+        assert_eq!(chain.difference_histogram(), Ok(expected));
+    }
+
+    #[test]
+    fn test_impossible_chain() {
+        let chain = AdapterChain::new(&[1, 2, 6]);
+        assert_eq!(
+            chain.difference_histogram(),
+            Err(ChainError::Gap { from: 2, to: 6 })
+        );
+        assert!(!chain.is_completable());
+        assert_eq!(chain.arrangements(), BigUint::from(0u32));
+    }
+
+    #[test]
+    fn test_configurable_tolerances() {
+        let chain = AdapterChain::new(&[2, 4, 5, 6])
+            .with_steps(1, 2)
+            .with_device_offset(1);
+        assert_eq!(chain.device(), 7);
+        assert_eq!(chain.arrangements(), BigUint::from(3u32));
+
+        let strict = AdapterChain::new(&[2, 4, 5, 6]).with_steps(2, 2);
+        assert!(!strict.is_completable());
+    }
+
+    #[test]
+    fn test_big_arrangement_count() {
+        let adapters: Vec<i32> = (1..=200).collect();
+        let res = AdapterChain::new(&adapters).arrangements();
+        assert!(u64::try_from(res.clone()).is_err());
+        assert_eq!(
+            res.to_string(),
+            "52622583840983769603765180599790256716084480555530641"
+        );
+    }
+
+    #[test]
+    fn test_iter_arrangements() {
+        let numbers = input_generator(INPUT1);
+        let all: Vec<Vec<i32>> = numbers.iter_arrangements().collect();
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]);
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 8);
+
+        let lazy: Vec<Vec<i32>> = input_generator(INPUT2)
+            .iter_arrangements()
+            .take(3)
+            .collect();
+        assert_eq!(lazy.len(), 3);
+    }
+
+    #[test]
+    fn test_sample_arrangement() {
+        let numbers = input_generator(INPUT1);
+        let all: HashSet<Vec<i32>> = numbers.iter_arrangements().collect();
+        let mut rng = StdRng::seed_from_u64(2020);
+        let samples: HashSet<Vec<i32>> = (0..200)
+            .map(|_| numbers.sample(&mut rng).unwrap())
+            .collect();
+        assert_eq!(samples, all);
+        assert_eq!(AdapterChain::new(&[1, 2, 6]).sample(&mut rng), None);
+    }
+
+    #[test]
+    fn test_shortest_and_longest() {
+        let numbers = input_generator(INPUT1);
+        assert_eq!(
+            numbers.shortest_arrangement(),
+            Some(vec![1, 4, 7, 10, 12, 15, 16, 19])
+        );
+        assert_eq!(
+            numbers.longest_arrangement(),
+            Some(vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19])
+        );
+        assert_eq!(AdapterChain::new(&[1, 2, 6]).shortest_arrangement(), None);
+    }
+
+    #[test]
+    fn test_mandatory_adapters() {
+        let numbers = input_generator(INPUT1);
+        assert_eq!(
+            numbers.mandatory_adapters(),
+            vec![1, 4, 7, 10, 12, 15, 16, 19]
+        );
+    }
 }
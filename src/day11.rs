@@ -1,4 +1,8 @@
+use crate::automaton::{Automaton, Neighbours};
 use lazy_static::lazy_static;
+use maplit::btreeset;
+use std::collections::BTreeSet;
+use std::str::FromStr;
 
 lazy_static! {
     static ref DIRECTIONS: [Coordinate; 8] = [
@@ -11,9 +15,25 @@ lazy_static! {
         Coordinate { x: 1, y: -1 },
         Coordinate { x: -1, y: -1 },
     ];
+    static ref HEX_EVEN_ROW: [Coordinate; 6] = [
+        Coordinate { x: 1, y: 0 },
+        Coordinate { x: -1, y: 0 },
+        Coordinate { x: -1, y: -1 },
+        Coordinate { x: 0, y: -1 },
+        Coordinate { x: -1, y: 1 },
+        Coordinate { x: 0, y: 1 },
+    ];
+    static ref HEX_ODD_ROW: [Coordinate; 6] = [
+        Coordinate { x: 1, y: 0 },
+        Coordinate { x: -1, y: 0 },
+        Coordinate { x: 0, y: -1 },
+        Coordinate { x: 1, y: -1 },
+        Coordinate { x: 0, y: 1 },
+        Coordinate { x: 1, y: 1 },
+    ];
 }
-#[derive(Eq, PartialEq, Clone)]
-enum Tile {
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum Tile {
     Empty,
     Floor,
     Occupied,
@@ -45,11 +65,10 @@ impl std::ops::Add for &Coordinate {
     }
 }
 
-type Map = Vec<Vec<Coordinate>>;
-type TileMap = Vec<Vec<Tile>>;
+pub type TileMap = Vec<Vec<Tile>>;
 
 #[aoc_generator(day11)]
-fn input_generator(input: &str) -> TileMap {
+pub fn input_generator(input: &str) -> TileMap {
     input
         .lines()
         .map(&str::trim)
@@ -67,142 +86,375 @@ fn input_generator(input: &str) -> TileMap {
         .collect()
 }
 
-#[allow(dead_code)]
-fn print_map(map: &TileMap) {
-    map.iter().for_each(|f| {
-        let s: String = f
-            .iter()
-            .map(|c| match c {
-                Tile::Occupied => '#',
-                Tile::Empty => 'L',
-                Tile::Floor => '.',
-            })
-            .collect();
-        println!("{}", s);
-    });
-    println!("");
+fn tile_char(tile: &Tile) -> char {
+    match tile {
+        Tile::Occupied => '#',
+        Tile::Empty => 'L',
+        Tile::Floor => '.',
+    }
 }
 
-#[aoc(day11, part1)]
-fn day11_part1(input: &TileMap) -> usize {
-    let mut map = input.clone();
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NeighbourhoodKind {
+    Moore,
+    VonNeumann,
+    LineOfSight(Option<usize>),
+    Hex,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FloorBehaviour {
+    Transparent,
+    Opaque,
+    Seat,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeatingRules {
+    pub birth: BTreeSet<usize>,
+    pub death: usize,
+    pub neighbourhood: NeighbourhoodKind,
+    pub floor: FloorBehaviour,
+}
 
-    loop {
-        let res = simulate_move(&map, 4, get_seats_to_check);
-        map = res.0;
-        if !res.1 {
-            break;
+impl SeatingRules {
+    pub fn part1() -> Self {
+        Self {
+            birth: btreeset! {0},
+            death: 4,
+            neighbourhood: NeighbourhoodKind::Moore,
+            floor: FloorBehaviour::Transparent,
+        }
+    }
+
+    pub fn part2() -> Self {
+        Self {
+            birth: btreeset! {0},
+            death: 5,
+            neighbourhood: NeighbourhoodKind::LineOfSight(None),
+            floor: FloorBehaviour::Transparent,
         }
     }
-    map.into_iter()
-        .flatten()
-        .filter(|t| *t == Tile::Occupied)
-        .count()
 }
-#[aoc(day11, part2)]
-fn day11_part2(input: &TileMap) -> usize {
-    let mut map = input.clone();
 
-    loop {
-        let res = simulate_move(&map, 5, seat_calc_part_2);
-        map = res.0;
-        if !res.1 {
-            break;
+impl FromStr for SeatingRules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = SeatingRules::part2();
+        for setting in s.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+            let (key, value) = setting
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=value, got {}", setting))?;
+            let parse_count = |v: &str| {
+                v.trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid count {} in {}", v, setting))
+            };
+            match key.trim() {
+                "birth" => {
+                    rules.birth = value
+                        .split(',')
+                        .map(parse_count)
+                        .collect::<Result<_, _>>()?
+                }
+                "death" => rules.death = parse_count(value)?,
+                "neighbourhood" => {
+                    rules.neighbourhood = match value.trim().split_once(':') {
+                        None if value.trim() == "moore" => NeighbourhoodKind::Moore,
+                        None if value.trim() == "vonneumann" => NeighbourhoodKind::VonNeumann,
+                        None if value.trim() == "hex" => NeighbourhoodKind::Hex,
+                        None if value.trim() == "sight" => NeighbourhoodKind::LineOfSight(None),
+                        Some(("sight", range)) => {
+                            NeighbourhoodKind::LineOfSight(Some(parse_count(range)?))
+                        }
+                        _ => return Err(format!("Unknown neighbourhood {}", value)),
+                    }
+                }
+                "floor" => {
+                    rules.floor = match value.trim() {
+                        "transparent" => FloorBehaviour::Transparent,
+                        "opaque" => FloorBehaviour::Opaque,
+                        "seat" => FloorBehaviour::Seat,
+                        _ => return Err(format!("Unknown floor behaviour {}", value)),
+                    }
+                }
+                _ => return Err(format!("Unknown setting {}", key)),
+            }
         }
+
+        Ok(rules)
     }
-    map.into_iter()
+}
+
+type SeatingAutomaton<R> = Automaton<Tile, Vec<Vec<usize>>, R>;
+
+fn seating_automaton(
+    input: &TileMap,
+    rules: &SeatingRules,
+) -> SeatingAutomaton<impl Fn(&Tile, Neighbours<Tile>) -> Tile> {
+    let cells: Vec<Tile> = input
+        .iter()
         .flatten()
-        .filter(|t| *t == Tile::Occupied)
+        .map(|t| match (t, rules.floor) {
+            (Tile::Floor, FloorBehaviour::Seat) => Tile::Empty,
+            _ => t.clone(),
+        })
+        .collect();
+    let birth = rules.birth.clone();
+    let death = rules.death;
+    Automaton::new(
+        cells,
+        neighbourhood(input, &rules.neighbourhood, rules.floor),
+        move |tile: &Tile, n: Neighbours<Tile>| {
+            let occupied = n.count(|t| *t == Tile::Occupied);
+            match tile {
+                Tile::Empty if birth.contains(&occupied) => Tile::Occupied,
+                Tile::Occupied if occupied >= death => Tile::Empty,
+                _ => tile.clone(),
+            }
+        },
+    )
+}
+
+pub fn simulate(input: &TileMap, rules: &SeatingRules) -> usize {
+    let mut automaton = seating_automaton(input, rules);
+    automaton.run_until_stable();
+
+    automaton
+        .cells()
+        .iter()
+        .filter(|t| **t == Tile::Occupied)
         .count()
 }
 
-fn get_seats_to_check(_: &[Vec<Tile>], coord: Coordinate) -> Map {
-    DIRECTIONS.iter().map(|c| vec![c + &coord]).collect()
+pub enum ImageFormat {
+    Pgm,
+    Ppm,
 }
 
-fn calculcate_visible_seats(
-    current: &Coordinate,
-    direction: &Coordinate,
-    limit_y: usize,
-    limit_x: usize,
-) -> Vec<Coordinate> {
-    let first: Coordinate = current + direction;
-    let mut coordinates = Vec::new();
+pub struct Recording {
+    width: usize,
+    frames: Vec<Vec<Tile>>,
+}
 
-    if first.x < 0 && first.x >= limit_x as i64 && first.y < 0 && first.y >= limit_y as i64 {
-        return vec![];
+impl Recording {
+    pub fn generations(&self) -> usize {
+        self.frames.len()
     }
-    coordinates.push(first);
 
-    loop {
-        let next = coordinates.last().unwrap() + &direction;
-        if next.y < 0 || next.y >= limit_y as i64 || next.x < 0 || next.x >= limit_x as i64 {
-            return coordinates;
-        }
-        coordinates.push(next);
+    pub fn occupancy(&self) -> Vec<usize> {
+        self.frames
+            .iter()
+            .map(|f| f.iter().filter(|t| **t == Tile::Occupied).count())
+            .collect()
+    }
+
+    pub fn changes(&self) -> Vec<usize> {
+        std::iter::once(0)
+            .chain(
+                self.frames
+                    .windows(2)
+                    .map(|w| w[0].iter().zip(w[1].iter()).filter(|(a, b)| a != b).count()),
+            )
+            .collect()
     }
-}
 
-fn count_occupied_adjacent(input: &[Vec<Tile>], to_check: Map) -> usize {
-    to_check
-        .into_iter()
-        .filter_map(|c| {
-            c.iter().find_map(|f| {
-                input
-                    .get(f.y as usize)
-                    .and_then(|r| r.get(f.x as usize))
-                    .filter(|c| **c != Tile::Floor)
+    pub fn frame_text(&self, generation: usize) -> String {
+        self.frames[generation]
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(tile_char)
+                    .chain(std::iter::once('\n'))
+                    .collect::<String>()
             })
-        })
-        .filter(|c| **c == Tile::Occupied)
-        .count()
+            .collect()
+    }
+
+    pub fn to_text_animation(&self) -> String {
+        let occupancy = self.occupancy();
+        let changes = self.changes();
+        (0..self.frames.len())
+            .map(|g| {
+                format!(
+                    "Generation {}: {} occupied, {} changed\n{}\n",
+                    g,
+                    occupancy[g],
+                    changes[g],
+                    self.frame_text(g)
+                )
+            })
+            .collect()
+    }
+
+    pub fn to_image_frames(&self, format: ImageFormat, scale: usize) -> Vec<Vec<u8>> {
+        let height = self
+            .frames
+            .first()
+            .map_or(0, |f| f.len() / self.width.max(1));
+        self.frames
+            .iter()
+            .map(|frame| {
+                let (magic, channels) = match format {
+                    ImageFormat::Pgm => ("P5", 1),
+                    ImageFormat::Ppm => ("P6", 3),
+                };
+                let mut image = format!(
+                    "{}\n{} {}\n255\n",
+                    magic,
+                    self.width * scale,
+                    height * scale
+                )
+                .into_bytes();
+                image.reserve(frame.len() * scale * scale * channels);
+                for row in frame.chunks(self.width) {
+                    for _ in 0..scale {
+                        for tile in row {
+                            let pixel: &[u8] = match (&format, tile) {
+                                (ImageFormat::Pgm, Tile::Floor) => &[0],
+                                (ImageFormat::Pgm, Tile::Empty) => &[128],
+                                (ImageFormat::Pgm, Tile::Occupied) => &[255],
+                                (ImageFormat::Ppm, Tile::Floor) => &[32, 32, 32],
+                                (ImageFormat::Ppm, Tile::Empty) => &[0, 160, 0],
+                                (ImageFormat::Ppm, Tile::Occupied) => &[220, 0, 0],
+                            };
+                            for _ in 0..scale {
+                                image.extend_from_slice(pixel);
+                            }
+                        }
+                    }
+                }
+                image
+            })
+            .collect()
+    }
 }
-fn seat_calc_part_2(map: &[Vec<Tile>], current: Coordinate) -> Map {
-    DIRECTIONS
-        .iter()
-        .map(|c| calculcate_visible_seats(&current, c, map.len(), map[0].len()))
-        .collect()
+
+pub fn record(input: &TileMap, rules: &SeatingRules) -> Recording {
+    let mut automaton = seating_automaton(input, rules);
+    let mut frames = vec![automaton.cells().to_vec()];
+    while automaton.step() {
+        frames.push(automaton.cells().to_vec());
+    }
+
+    Recording {
+        width: input.first().map_or(0, |r| r.len()),
+        frames,
+    }
 }
 
-fn simulate_move(
-    input: &TileMap,
-    tolerance: usize,
-    seat_calc: impl Fn(&[Vec<Tile>], Coordinate) -> Map,
-) -> (TileMap, bool) {
-    let mut result: TileMap = Vec::with_capacity(input.len());
-    let mut changes = false;
-    for y in input.iter().enumerate() {
-        let mut new_row: Vec<Tile> = Vec::with_capacity(input[0].len());
-        for x in y.1.iter().enumerate() {
-            if *x.1 == Tile::Floor {
-                new_row.push(Tile::Floor);
-            } else if *x.1 == Tile::Empty {
-                let to_check = seat_calc(input, (x.0, y.0).into());
-                if count_occupied_adjacent(input, to_check) == 0 {
-                    new_row.push(Tile::Occupied);
-                    changes = true;
-                } else {
-                    new_row.push(Tile::Empty);
-                }
-            } else {
-                let to_check = seat_calc(input, (x.0, y.0).into());
-                if count_occupied_adjacent(input, to_check) >= tolerance {
-                    new_row.push(Tile::Empty);
-                    changes = true;
-                } else {
-                    new_row.push(Tile::Occupied);
-                }
+fn seats_along(
+    map: &TileMap,
+    directions: impl Fn(usize) -> &'static [Coordinate],
+    range: Option<usize>,
+    floor: FloorBehaviour,
+) -> Vec<Vec<usize>> {
+    let height = map.len();
+    let width = map[0].len();
+    let is_seat = |c: &Coordinate| {
+        floor == FloorBehaviour::Seat || map[c.y as usize][c.x as usize] != Tile::Floor
+    };
+
+    (0..height * width)
+        .map(|cell| {
+            let current: Coordinate = (cell % width, cell / width).into();
+            if !is_seat(&current) {
+                return Vec::new();
             }
+            directions(cell / width)
+                .iter()
+                .filter_map(|d| {
+                    let mut visible =
+                        calculcate_visible_seats(&current, d, height, width, range).into_iter();
+                    match floor {
+                        FloorBehaviour::Transparent => visible.find(|c| is_seat(c)),
+                        _ => visible.next().filter(|c| is_seat(c)),
+                    }
+                    .map(|c| c.y as usize * width + c.x as usize)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn visible_seats(map: &TileMap, range: Option<usize>) -> Vec<Vec<usize>> {
+    seats_along(map, |_| &DIRECTIONS[..], range, FloorBehaviour::Transparent)
+}
+
+pub fn neighbourhood(
+    map: &TileMap,
+    kind: &NeighbourhoodKind,
+    floor: FloorBehaviour,
+) -> Vec<Vec<usize>> {
+    match kind {
+        NeighbourhoodKind::Moore => seats_along(map, |_| &DIRECTIONS[..], Some(1), floor),
+        NeighbourhoodKind::VonNeumann => seats_along(map, |_| &DIRECTIONS[..4], Some(1), floor),
+        NeighbourhoodKind::LineOfSight(range) => {
+            seats_along(map, |_| &DIRECTIONS[..], *range, floor)
         }
-        result.push(new_row);
+        NeighbourhoodKind::Hex => seats_along(
+            map,
+            |y| match y % 2 {
+                0 => &HEX_EVEN_ROW[..],
+                _ => &HEX_ODD_ROW[..],
+            },
+            Some(1),
+            floor,
+        ),
+    }
+}
+
+#[aoc(day11, part1)]
+fn day11_part1(input: &TileMap) -> usize {
+    simulate(input, &SeatingRules::part1())
+}
+
+#[aoc(day11, part2)]
+fn day11_part2(input: &TileMap) -> usize {
+    simulate(input, &SeatingRules::part2())
+}
+
+#[aoc(day11, part2, rules)]
+fn day11_custom_rules(input: &TileMap) -> usize {
+    let rules = std::env::var("DAY11_RULES")
+        .map(|r| {
+            r.parse()
+                .unwrap_or_else(|e| panic!("Invalid DAY11_RULES: {}", e))
+        })
+        .unwrap_or_else(|_| SeatingRules::part2());
+    simulate(input, &rules)
+}
+
+fn calculcate_visible_seats(
+    current: &Coordinate,
+    direction: &Coordinate,
+    limit_y: usize,
+    limit_x: usize,
+    range: Option<usize>,
+) -> Vec<Coordinate> {
+    let in_bounds =
+        |c: &Coordinate| c.x >= 0 && c.x < limit_x as i64 && c.y >= 0 && c.y < limit_y as i64;
+    let mut coordinates = Vec::new();
+    let mut next = current + direction;
+
+    while in_bounds(&next) && range.is_none_or(|r| coordinates.len() < r) {
+        let following = &next + direction;
+        coordinates.push(next);
+        next = following;
     }
 
-    (result, changes)
+    coordinates
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{calculcate_visible_seats, day11_part1, day11_part2, input_generator, Coordinate};
+    use super::{
+        calculcate_visible_seats, day11_part1, day11_part2, input_generator, neighbourhood, record,
+        simulate, visible_seats, Coordinate, FloorBehaviour, ImageFormat, NeighbourhoodKind,
+        SeatingRules, Tile, TileMap,
+    };
+    use proptest::prelude::*;
     const INPUT1: &'static str = "#.##.##.##
         #######.##
         #.#.#..#..
@@ -230,7 +482,7 @@ mod tests {
     #[test]
     fn test_calc_visible_seats_1() {
         let current = Coordinate { x: 3, y: 2 };
-        let res = calculcate_visible_seats(&current, &Coordinate { x: -1, y: -1 }, 5, 4);
+        let res = calculcate_visible_seats(&current, &Coordinate { x: -1, y: -1 }, 5, 4, None);
         assert_eq!(
             vec![Coordinate { x: 2, y: 1 }, Coordinate { x: 1, y: 0 }],
             res
@@ -240,7 +492,214 @@ mod tests {
     #[test]
     fn test_calc_visible_seats_2() {
         let current = Coordinate { x: 3, y: 2 };
-        let res = calculcate_visible_seats(&current, &Coordinate { x: 1, y: -1 }, 5, 4);
-        assert_eq!(vec![Coordinate { x: 4, y: 1 }], res);
+        let res = calculcate_visible_seats(&current, &Coordinate { x: 1, y: -1 }, 5, 4, None);
+        assert!(res.is_empty());
+    }
+
+    #[test]
+    fn test_visible_seats() {
+        let map = input_generator(
+            "L.L
+            ...
+            #.L",
+        );
+        let adjacent = visible_seats(&map, Some(1));
+        assert!(adjacent.iter().all(|n| n.is_empty()));
+
+        let mut visible = visible_seats(&map, None);
+        visible.iter_mut().for_each(|n| n.sort_unstable());
+        assert_eq!(visible[0], vec![2, 6, 8]);
+        assert_eq!(visible[8], vec![0, 2, 6]);
+        assert!(visible[4].is_empty());
+    }
+
+    #[test]
+    fn test_calc_visible_seats_leaving_map() {
+        let current = Coordinate { x: 0, y: 0 };
+        let res = calculcate_visible_seats(&current, &Coordinate { x: -1, y: -1 }, 5, 4, None);
+        assert!(res.is_empty());
+        let res = calculcate_visible_seats(&current, &Coordinate { x: 1, y: 0 }, 1, 1, None);
+        assert!(res.is_empty());
+    }
+
+    #[test]
+    fn test_calc_visible_seats_range() {
+        let current = Coordinate { x: 0, y: 0 };
+        let res = calculcate_visible_seats(&current, &Coordinate { x: 1, y: 1 }, 5, 4, Some(2));
+        assert_eq!(
+            vec![Coordinate { x: 1, y: 1 }, Coordinate { x: 2, y: 2 }],
+            res
+        );
+    }
+
+    #[test]
+    fn test_record_statistics() {
+        let map = input_generator(INPUT1);
+        let recording = record(&map, &SeatingRules::part1());
+        assert_eq!(recording.generations(), 5);
+        assert_eq!(recording.occupancy().last(), Some(&37));
+        assert_eq!(recording.changes()[0], 0);
+        assert!(recording.changes()[1..].iter().all(|c| *c > 0));
+        assert!(recording
+            .frame_text(0)
+            .starts_with("#.##.##.##\n#######.##\n"));
+        assert!(recording
+            .to_text_animation()
+            .starts_with("Generation 0: 71 occupied, 0 changed\n#.##.##.##\n"));
+    }
+
+    #[test]
+    fn test_record_image_frames() {
+        let map = input_generator("L.\n#L");
+        let recording = record(&map, &SeatingRules::part1());
+        let frames = recording.to_image_frames(ImageFormat::Pgm, 1);
+        assert_eq!(frames.len(), recording.generations());
+        assert_eq!(frames[0], b"P5\n2 2\n255\n\x80\x00\xff\x80".to_vec());
+
+        let scaled = recording.to_image_frames(ImageFormat::Ppm, 2);
+        let header = b"P6\n4 4\n255\n";
+        assert!(scaled[0].starts_with(header));
+        assert_eq!(scaled[0].len(), header.len() + 4 * 4 * 3);
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules: SeatingRules = "birth=0,1; death=3; neighbourhood=sight:2".parse().unwrap();
+        assert_eq!(rules.birth.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(rules.death, 3);
+        assert_eq!(rules.neighbourhood, NeighbourhoodKind::LineOfSight(Some(2)));
+
+        assert_eq!("".parse::<SeatingRules>(), Ok(SeatingRules::part2()));
+        assert!("death=x".parse::<SeatingRules>().is_err());
+        assert!("neighbourhood=triangle".parse::<SeatingRules>().is_err());
+        assert!("speed=3".parse::<SeatingRules>().is_err());
+
+        let rules: SeatingRules = "floor=opaque".parse().unwrap();
+        assert_eq!(rules.floor, FloorBehaviour::Opaque);
+        assert!("floor=lava".parse::<SeatingRules>().is_err());
+    }
+
+    #[test]
+    fn test_floor_behaviour() {
+        let map = input_generator("L.L");
+        let sight = NeighbourhoodKind::LineOfSight(None);
+        assert_eq!(
+            neighbourhood(&map, &sight, FloorBehaviour::Transparent),
+            vec![vec![2], vec![], vec![0]]
+        );
+        assert_eq!(
+            neighbourhood(&map, &sight, FloorBehaviour::Opaque),
+            vec![vec![], vec![], vec![]]
+        );
+        assert_eq!(
+            neighbourhood(&map, &sight, FloorBehaviour::Seat),
+            vec![vec![1], vec![2, 0], vec![1]]
+        );
+
+        let floor = input_generator("...");
+        assert_eq!(simulate(&floor, &SeatingRules::part1()), 0);
+        let seated: SeatingRules = "death=4;neighbourhood=moore;floor=seat".parse().unwrap();
+        assert_eq!(simulate(&floor, &seated), 3);
+    }
+
+    #[test]
+    fn test_configured_rules_match_parts() {
+        let map = input_generator(INPUT1);
+        let part1: SeatingRules = "death=4;neighbourhood=moore".parse().unwrap();
+        assert_eq!(simulate(&map, &part1), 37);
+        let part2: SeatingRules = "birth=0;death=5;neighbourhood=sight".parse().unwrap();
+        assert_eq!(simulate(&map, &part2), 26);
+    }
+
+    #[test]
+    fn test_neighbourhood_kinds() {
+        let map = input_generator(
+            "LLL
+            LLL
+            LLL",
+        );
+        let count = |kind| {
+            neighbourhood(&map, &kind, FloorBehaviour::Transparent)
+                .iter()
+                .map(|n| n.len())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            count(NeighbourhoodKind::Moore),
+            vec![3, 5, 3, 5, 8, 5, 3, 5, 3]
+        );
+        assert_eq!(
+            count(NeighbourhoodKind::VonNeumann),
+            vec![2, 3, 2, 3, 4, 3, 2, 3, 2]
+        );
+        assert_eq!(
+            count(NeighbourhoodKind::Hex),
+            vec![2, 4, 3, 5, 6, 3, 2, 4, 3]
+        );
+        let mut hex =
+            neighbourhood(&map, &NeighbourhoodKind::Hex, FloorBehaviour::Transparent)[4].clone();
+        hex.sort_unstable();
+        assert_eq!(hex, vec![1, 2, 3, 5, 7, 8]);
+    }
+
+    fn brute_force_visible(map: &TileMap, x: usize, y: usize, range: Option<usize>) -> Vec<usize> {
+        let width = map[0].len();
+        let mut visible = Vec::new();
+        for (oy, row) in map.iter().enumerate() {
+            for (ox, tile) in row.iter().enumerate() {
+                let (dx, dy) = (ox as i64 - x as i64, oy as i64 - y as i64);
+                let distance = dx.abs().max(dy.abs());
+                let on_ray = dx == 0 || dy == 0 || dx.abs() == dy.abs();
+                if *tile == Tile::Floor || distance == 0 || !on_ray {
+                    continue;
+                }
+                if range.is_some_and(|r| distance as usize > r) {
+                    continue;
+                }
+                let blocked = (1..distance).any(|k| {
+                    let cx = x as i64 + dx / distance * k;
+                    let cy = y as i64 + dy / distance * k;
+                    map[cy as usize][cx as usize] != Tile::Floor
+                });
+                if !blocked {
+                    visible.push(oy * width + ox);
+                }
+            }
+        }
+        visible
+    }
+
+    fn tile_map() -> impl Strategy<Value = TileMap> {
+        (1usize..8, 1usize..8).prop_flat_map(|(w, h)| {
+            prop::collection::vec(
+                prop::collection::vec(
+                    prop_oneof![Just(Tile::Floor), Just(Tile::Empty), Just(Tile::Occupied)],
+                    w,
+                ),
+                h,
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_visible_seats_match_brute_force(
+            map in tile_map(),
+            range in prop::option::of(1usize..5),
+        ) {
+            let width = map[0].len();
+            let visible = visible_seats(&map, range);
+            for (cell, neighbours) in visible.into_iter().enumerate() {
+                let (x, y) = (cell % width, cell / width);
+                let mut neighbours = neighbours;
+                neighbours.sort_unstable();
+                let expected = if map[y][x] == Tile::Floor {
+                    Vec::new()
+                } else {
+                    brute_force_visible(&map, x, y, range)
+                };
+                prop_assert_eq!(neighbours, expected);
+            }
+        }
     }
 }
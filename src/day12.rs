@@ -1,6 +1,8 @@
 use std::borrow::Borrow;
+use std::fmt::Write;
 
-enum Instruction {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
     North(i64),
     West(i64),
     East(i64),
@@ -10,30 +12,10 @@ enum Instruction {
     Forward(i64),
 }
 
-#[derive(Eq, PartialEq, Debug)]
-struct Coordinate {
-    x: i64,
-    y: i64,
-}
-
-impl Coordinate {
-    pub fn rotate(&self, degrees: i64) -> Self {
-        match degrees {
-            180 => Coordinate {
-                x: -self.x,
-                y: -self.y,
-            },
-            90 => Coordinate {
-                x: self.y,
-                y: -self.x,
-            },
-            270 => Coordinate {
-                x: -self.y,
-                y: self.x,
-            },
-            _ => panic!("Not a 90 degree rotation"),
-        }
-    }
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Coordinate {
+    pub x: i64,
+    pub y: i64,
 }
 
 impl std::ops::Add for &Coordinate {
@@ -63,107 +45,485 @@ impl std::ops::Mul<i64> for &Coordinate {
     }
 }
 
-impl Ship {
-    pub fn new() -> Self {
-        Self {
-            facing: 90,
-            position: Coordinate { x: 0, y: 0 },
+// Positions are kept in fixed point so that headings which are not multiples of 90 degrees can
+// still be followed. Right angles are handled exactly.
+const SCALE: i64 = 1_000_000;
+
+// Divides by SCALE, rounding half away from zero.
+fn unscale(value: i128) -> i64 {
+    let rounded = (value.abs() + SCALE as i128 / 2) / SCALE as i128;
+    (rounded * value.signum()) as i64
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct FixedCoordinate {
+    x: i64,
+    y: i64,
+}
+
+impl From<Coordinate> for FixedCoordinate {
+    fn from(coord: Coordinate) -> Self {
+        FixedCoordinate {
+            x: coord.x * SCALE,
+            y: coord.y * SCALE,
         }
     }
+}
+
+impl std::ops::AddAssign for FixedCoordinate {
+    fn add_assign(&mut self, other: FixedCoordinate) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
 
-    pub fn travel(&mut self, instruction: &Instruction) {
-        match instruction {
-            Instruction::North(_)
-            | Instruction::East(_)
-            | Instruction::West(_)
-            | Instruction::South(_) => self.travel_by_direction(instruction),
-            Instruction::Left(_) | Instruction::Right(_) => self.rotate(instruction),
-            Instruction::Forward(d) => self.travel_by_facing(d),
+impl FixedCoordinate {
+    pub fn round(&self) -> Coordinate {
+        Coordinate {
+            x: unscale(self.x as i128),
+            y: unscale(self.y as i128),
+        }
+    }
+
+    fn rotate(&self, degrees: i64, mode: AngleMode) -> Result<Self, NavigationError> {
+        let (sin, cos) = mode.sin_cos(degrees)?;
+        let turn = |a: i64, b: i64| unscale(a as i128 * cos as i128 + b as i128 * sin as i128);
+        Ok(FixedCoordinate {
+            x: turn(self.x, self.y),
+            y: turn(self.y, -self.x),
+        })
+    }
+
+    fn scaled(&self, factor: i64) -> Self {
+        FixedCoordinate {
+            x: self.x * factor,
+            y: self.y * factor,
         }
     }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum NavigationError {
+    UnsupportedAngle(i64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AngleMode {
+    RightAngles,
+    Arbitrary,
+}
+
+impl AngleMode {
+    // Returns sine and cosine of a clockwise angle from north, in fixed point.
+    fn sin_cos(&self, degrees: i64) -> Result<(i64, i64), NavigationError> {
+        match (degrees.rem_euclid(360), self) {
+            (0, _) => Ok((0, SCALE)),
+            (90, _) => Ok((SCALE, 0)),
+            (180, _) => Ok((0, -SCALE)),
+            (270, _) => Ok((-SCALE, 0)),
+            (_, AngleMode::RightAngles) => Err(NavigationError::UnsupportedAngle(degrees)),
+            (d, AngleMode::Arbitrary) => {
+                let radians = (d as f64).to_radians();
+                Ok((
+                    (radians.sin() * SCALE as f64).round() as i64,
+                    (radians.cos() * SCALE as f64).round() as i64,
+                ))
+            }
+        }
+    }
+}
+
+impl Coordinate {
+    pub fn rotate(&self, degrees: i64, mode: AngleMode) -> Result<Self, NavigationError> {
+        FixedCoordinate::from(*self)
+            .rotate(degrees, mode)
+            .map(|c| c.round())
+    }
+
+    pub fn manhattan(&self) -> u64 {
+        self.x.unsigned_abs() + self.y.unsigned_abs()
+    }
+}
+
+pub trait Navigator {
+    fn translate(&mut self, offset: Coordinate);
+    fn turn(&mut self, degrees: i64) -> Result<(), NavigationError>;
+    fn forward(&mut self, distance: i64) -> Result<(), NavigationError>;
+    fn fixed_position(&self) -> FixedCoordinate;
+
+    fn fixed_waypoint(&self) -> Option<FixedCoordinate> {
+        None
+    }
+
+    fn position(&self) -> Coordinate {
+        self.fixed_position().round()
+    }
+
+    fn waypoint(&self) -> Option<Coordinate> {
+        self.fixed_waypoint().map(|w| w.round())
+    }
 
-    fn travel_by_direction(&mut self, instruction: &Instruction) {
+    fn perform(&mut self, instruction: &Instruction) -> Result<(), NavigationError> {
         match instruction {
-            Instruction::North(d) => self.position.y += d,
-            Instruction::South(d) => self.position.y -= d,
-            Instruction::West(d) => self.position.x -= d,
-            Instruction::East(d) => self.position.x += d,
-            _ => panic!("Not a direction instruction"),
-        }
-    }
-
-    fn rotate(&mut self, instruction: &Instruction) {
-        let angle = match instruction {
-            Instruction::Right(d) => *d,
-            Instruction::Left(d) => 360 - *d,
-            _ => panic!("Not a rotation instruction"),
-        } as u16;
-        self.facing += angle;
-        self.facing %= 360;
-    }
-
-    fn travel_by_facing(&mut self, distance: &i64) {
-        let instruction = match self.facing {
-            0 => Instruction::North(*distance),
-            90 => Instruction::East(*distance),
-            180 => Instruction::South(*distance),
-            270 => Instruction::West(*distance),
-            _ => panic!("{} is not an even angle", self.facing),
+            Instruction::North(d) => self.translate(Coordinate { x: 0, y: *d }),
+            Instruction::South(d) => self.translate(Coordinate { x: 0, y: -*d }),
+            Instruction::East(d) => self.translate(Coordinate { x: *d, y: 0 }),
+            Instruction::West(d) => self.translate(Coordinate { x: -*d, y: 0 }),
+            Instruction::Right(d) => self.turn(*d)?,
+            Instruction::Left(d) => self.turn(-*d)?,
+            Instruction::Forward(d) => self.forward(*d)?,
+        }
+        Ok(())
+    }
+
+    fn navigate(&mut self, instructions: &[Instruction]) -> Result<u64, NavigationError> {
+        for i in instructions {
+            self.perform(i)?;
+        }
+        Ok(self.position().manhattan())
+    }
+
+    fn navigate_route(&mut self, instructions: &[Instruction]) -> Result<Route, NavigationError> {
+        let mut route = Route {
+            points: vec![(self.fixed_position(), self.fixed_waypoint())],
         };
-        self.travel_by_direction(&instruction);
+        for i in instructions {
+            self.perform(i)?;
+            route
+                .points
+                .push((self.fixed_position(), self.fixed_waypoint()));
+        }
+        Ok(route)
     }
 }
 
-struct Ship {
-    pub facing: u16,
-    pub position: Coordinate,
+pub struct Route {
+    points: Vec<(FixedCoordinate, Option<FixedCoordinate>)>,
+}
+
+impl Route {
+    pub fn positions(&self) -> Vec<Coordinate> {
+        self.points.iter().map(|p| p.0.round()).collect()
+    }
+
+    pub fn waypoints(&self) -> Vec<Option<Coordinate>> {
+        self.points.iter().map(|p| p.1.map(|w| w.round())).collect()
+    }
+
+    pub fn bounding_box(&self) -> (Coordinate, Coordinate) {
+        let positions = self.positions();
+        let min = Coordinate {
+            x: positions.iter().map(|p| p.x).min().unwrap(),
+            y: positions.iter().map(|p| p.y).min().unwrap(),
+        };
+        let max = Coordinate {
+            x: positions.iter().map(|p| p.x).max().unwrap(),
+            y: positions.iter().map(|p| p.y).max().unwrap(),
+        };
+        (min, max)
+    }
+
+    pub fn distance_travelled(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|w| {
+                let dx = (w[1].0.x - w[0].0.x) as f64 / SCALE as f64;
+                let dy = (w[1].0.y - w[0].0.y) as f64 / SCALE as f64;
+                dx.hypot(dy)
+            })
+            .sum()
+    }
+
+    pub fn furthest_point(&self) -> Coordinate {
+        self.positions()
+            .into_iter()
+            .max_by_key(Coordinate::manhattan)
+            .unwrap()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,x,y,waypoint_x,waypoint_y\n");
+        for (step, (position, waypoint)) in
+            self.positions().iter().zip(self.waypoints()).enumerate()
+        {
+            let (wx, wy) = waypoint
+                .map(|w| (w.x.to_string(), w.y.to_string()))
+                .unwrap_or_default();
+            writeln!(csv, "{},{},{},{},{}", step, position.x, position.y, wx, wy).unwrap();
+        }
+        csv
+    }
+
+    pub fn to_svg(&self) -> String {
+        let (min, max) = self.bounding_box();
+        let margin = ((max.x - min.x).max(max.y - min.y) / 20).max(1);
+        let points: Vec<String> = self
+            .positions()
+            .iter()
+            .map(|p| format!("{},{}", p.x, -p.y))
+            .collect();
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n  \
+             <polyline fill=\"none\" stroke=\"black\" vector-effect=\"non-scaling-stroke\" points=\"{}\"/>\n\
+             </svg>\n",
+            min.x - margin,
+            -max.y - margin,
+            max.x - min.x + 2 * margin,
+            max.y - min.y + 2 * margin,
+            points.join(" ")
+        )
+    }
 }
 
-struct Ship2 {
-    pub position: Coordinate,
-    pub waypoint: Coordinate,
+#[derive(Clone)]
+pub struct Ship {
+    pub facing: i64,
+    pub mode: AngleMode,
+    position: FixedCoordinate,
 }
 
-impl Ship2 {
+impl Ship {
     pub fn new() -> Self {
         Self {
-            position: Coordinate { x: 0, y: 0 },
-            waypoint: Coordinate { x: 10, y: 1 },
+            facing: 90,
+            position: FixedCoordinate { x: 0, y: 0 },
+            mode: AngleMode::RightAngles,
         }
     }
 
-    pub fn perform_instruction(&mut self, instruction: &Instruction) {
-        match instruction {
-            Instruction::North(_)
-            | Instruction::East(_)
-            | Instruction::West(_)
-            | Instruction::South(_) => self.move_waypoint(instruction),
-            Instruction::Left(_) | Instruction::Right(_) => self.rotate(instruction),
-            Instruction::Forward(d) => self.forward(d),
-        }
+    pub fn with_angle_mode(mut self, mode: AngleMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl Default for Ship {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Navigator for Ship {
+    fn translate(&mut self, offset: Coordinate) {
+        self.position += FixedCoordinate::from(offset);
     }
 
-    pub fn rotate(&mut self, instruction: &Instruction) {
-        let degree = match *instruction {
-            Instruction::Left(d) => 360 - d,
-            Instruction::Right(d) => d,
-            _ => panic!("Not a rotation instruction"),
+    fn turn(&mut self, degrees: i64) -> Result<(), NavigationError> {
+        let facing = (self.facing + degrees).rem_euclid(360);
+        self.mode.sin_cos(facing)?;
+        self.facing = facing;
+        Ok(())
+    }
+
+    fn forward(&mut self, distance: i64) -> Result<(), NavigationError> {
+        let (sin, cos) = self.mode.sin_cos(self.facing)?;
+        self.position += FixedCoordinate {
+            x: sin * distance,
+            y: cos * distance,
         };
-        self.waypoint = self.waypoint.rotate(degree);
+        Ok(())
     }
 
-    pub fn move_waypoint(&mut self, instruction: &Instruction) {
-        match instruction {
-            Instruction::North(d) => self.waypoint.y += d,
-            Instruction::South(d) => self.waypoint.y -= d,
-            Instruction::West(d) => self.waypoint.x -= d,
-            Instruction::East(d) => self.waypoint.x += d,
-            _ => panic!("Not a direction instruction"),
+    fn fixed_position(&self) -> FixedCoordinate {
+        self.position
+    }
+}
+
+#[derive(Clone)]
+pub struct WaypointShip {
+    pub mode: AngleMode,
+    position: FixedCoordinate,
+    waypoint: FixedCoordinate,
+}
+
+impl WaypointShip {
+    pub fn new() -> Self {
+        Self {
+            position: FixedCoordinate { x: 0, y: 0 },
+            waypoint: Coordinate { x: 10, y: 1 }.into(),
+            mode: AngleMode::RightAngles,
+        }
+    }
+
+    pub fn with_angle_mode(mut self, mode: AngleMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl Default for WaypointShip {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Navigator for WaypointShip {
+    fn translate(&mut self, offset: Coordinate) {
+        self.waypoint += FixedCoordinate::from(offset);
+    }
+
+    fn turn(&mut self, degrees: i64) -> Result<(), NavigationError> {
+        self.waypoint = self.waypoint.rotate(degrees, self.mode)?;
+        Ok(())
+    }
+
+    fn forward(&mut self, distance: i64) -> Result<(), NavigationError> {
+        self.position += self.waypoint.scaled(distance);
+        Ok(())
+    }
+
+    fn fixed_position(&self) -> FixedCoordinate {
+        self.position
+    }
+
+    fn fixed_waypoint(&self) -> Option<FixedCoordinate> {
+        Some(self.waypoint)
+    }
+}
+
+fn axis_moves(delta: Coordinate) -> Vec<Instruction> {
+    let x = match delta.x {
+        0 => None,
+        x if x > 0 => Some(Instruction::East(x)),
+        x => Some(Instruction::West(-x)),
+    };
+    let y = match delta.y {
+        0 => None,
+        y if y > 0 => Some(Instruction::North(y)),
+        y => Some(Instruction::South(-y)),
+    };
+    x.into_iter().chain(y).collect()
+}
+
+fn turn_by(degrees: i64) -> Option<Instruction> {
+    match degrees.rem_euclid(360) {
+        0 => None,
+        270 => Some(Instruction::Left(90)),
+        d => Some(Instruction::Right(d)),
+    }
+}
+
+fn difference(from: &Coordinate, to: &Coordinate) -> Coordinate {
+    Coordinate {
+        x: to.x - from.x,
+        y: to.y - from.y,
+    }
+}
+
+// Returns the positive multiplier k for which k * direction == delta, if any.
+fn multiplier(direction: &Coordinate, delta: &Coordinate) -> Option<i64> {
+    let k = match (direction.x, direction.y) {
+        (0, 0) => return None,
+        (0, y) => delta.y / y,
+        (x, _) => delta.x / x,
+    };
+    Some(k).filter(|k| *k > 0 && &(direction * *k) == delta)
+}
+
+impl Ship {
+    pub fn plan_to(&self, target: Coordinate) -> Vec<Instruction> {
+        axis_moves(difference(&self.position(), &target))
+    }
+
+    pub fn optimize(
+        &self,
+        instructions: &[Instruction],
+    ) -> Result<Vec<Instruction>, NavigationError> {
+        let mut end = self.clone();
+        end.navigate(instructions)?;
+
+        let mut optimized = self.plan_to(end.position());
+        optimized.extend(turn_by(end.facing - self.facing));
+        if optimized.len() >= instructions.len() {
+            return Ok(instructions.to_vec());
         }
+        Ok(optimized)
     }
+}
+
+impl WaypointShip {
+    pub fn plan_to(&self, target: Coordinate) -> Vec<Instruction> {
+        let delta = difference(&self.position(), &target);
+        if delta == (Coordinate { x: 0, y: 0 }) {
+            return Vec::new();
+        }
+        let waypoint = self.waypoint.round();
+
+        for degrees in [0, 90, 180, 270].iter() {
+            let rotated = waypoint.rotate(*degrees, AngleMode::RightAngles).unwrap();
+            if let Some(k) = multiplier(&rotated, &delta) {
+                return turn_by(*degrees)
+                    .into_iter()
+                    .chain(std::iter::once(Instruction::Forward(k)))
+                    .collect();
+            }
+        }
 
-    pub fn forward(&mut self, multiplier: &i64) {
-        self.position += &self.waypoint * *multiplier;
+        let shifted_x = match waypoint.y {
+            0 if delta.y == 0 => Some(1),
+            0 => None,
+            y => Some(delta.y / y).filter(|k| *k > 0 && k * y == delta.y && delta.x % k == 0),
+        }
+        .map(|k| {
+            (
+                Coordinate {
+                    x: delta.x / k - waypoint.x,
+                    y: 0,
+                },
+                k,
+            )
+        });
+        let shifted_y = match waypoint.x {
+            0 if delta.x == 0 => Some(1),
+            0 => None,
+            x => Some(delta.x / x).filter(|k| *k > 0 && k * x == delta.x && delta.y % k == 0),
+        }
+        .map(|k| {
+            (
+                Coordinate {
+                    x: 0,
+                    y: delta.y / k - waypoint.y,
+                },
+                k,
+            )
+        });
+        if let Some((shift, k)) = shifted_x.or(shifted_y) {
+            let mut plan = axis_moves(shift);
+            plan.push(Instruction::Forward(k));
+            return plan;
+        }
+
+        let mut plan = axis_moves(difference(&waypoint, &delta));
+        plan.push(Instruction::Forward(1));
+        plan
+    }
+
+    pub fn optimize(
+        &self,
+        instructions: &[Instruction],
+    ) -> Result<Vec<Instruction>, NavigationError> {
+        let mut end = self.clone();
+        end.navigate(instructions)?;
+
+        let mut optimized = self.plan_to(end.position());
+        let mut planned = self.clone();
+        planned.navigate(&optimized)?;
+
+        let current = planned.waypoint.round();
+        let target = end.waypoint.round();
+        let rotation = [90, 180, 270]
+            .iter()
+            .find(|d| current.rotate(**d, AngleMode::RightAngles).unwrap() == target);
+        match rotation {
+            _ if current == target => {}
+            Some(degrees) => optimized.extend(turn_by(*degrees)),
+            None => optimized.extend(axis_moves(difference(&current, &target))),
+        }
+
+        if optimized.len() >= instructions.len() {
+            return Ok(instructions.to_vec());
+        }
+        Ok(optimized)
     }
 }
 
@@ -193,38 +553,36 @@ fn input_generator(input: &str) -> Vec<Instruction> {
 
 #[aoc(day12, part1)]
 fn day12_part1(input: &[Instruction]) -> u64 {
-    let mut ship = Ship::new();
-    for i in input {
-        ship.travel(i);
-    }
-
-    ship.position.x.abs() as u64 + ship.position.y.abs() as u64
+    Ship::new()
+        .navigate(input)
+        .unwrap_or_else(|e| panic!("Failed to navigate: {:?}", e))
 }
 
 #[aoc(day12, part2)]
-fn day12_part2(input: &Vec<Instruction>) -> u64 {
-    let mut ship = Ship2::new();
-    for i in input {
-        ship.perform_instruction(i);
-    }
-
-    ship.position.x.abs() as u64 + ship.position.y.abs() as u64
+fn day12_part2(input: &[Instruction]) -> u64 {
+    WaypointShip::new()
+        .navigate(input)
+        .unwrap_or_else(|e| panic!("Failed to navigate: {:?}", e))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{day12_part1, input_generator, Coordinate, Instruction, Ship, Ship2};
+    use super::{
+        day12_part1, day12_part2, input_generator, AngleMode, Coordinate, Instruction,
+        NavigationError, Navigator, Ship, WaypointShip,
+    };
+    use proptest::prelude::*;
 
     #[test]
     fn test_rotate_right() {
         let mut ship = Ship::new();
-        ship.rotate(&Instruction::Right(90));
+        ship.perform(&Instruction::Right(90)).unwrap();
         assert_eq!(ship.facing, 180);
     }
     #[test]
     fn test_rotate_left() {
         let mut ship = Ship::new();
-        ship.rotate(&Instruction::Left(180));
+        ship.perform(&Instruction::Left(180)).unwrap();
         assert_eq!(ship.facing, 270);
     }
 
@@ -240,11 +598,238 @@ mod tests {
         assert_eq!(res, 25);
     }
 
+    #[test]
+    fn test_given_part_2() {
+        let input = "F10
+        N3
+        F7
+        R90
+        F11";
+        let instructions = input_generator(input);
+        let res = day12_part2(&instructions);
+        assert_eq!(res, 286);
+    }
+
     #[test]
     pub fn test_rotate_waypoint() {
-        let mut ship = Ship2::new();
-        ship.waypoint = Coordinate { x: 10, y: 4 };
-        ship.rotate(&Instruction::Right(90));
-        assert_eq!(ship.waypoint, Coordinate { x: 4, y: -10 });
+        let mut ship = WaypointShip::new();
+        ship.translate(Coordinate { x: 0, y: 3 });
+        ship.perform(&Instruction::Right(90)).unwrap();
+        assert_eq!(ship.waypoint(), Some(Coordinate { x: 4, y: -10 }));
+    }
+
+    #[test]
+    fn test_unsupported_angle() {
+        let mut ship = Ship::new();
+        assert_eq!(
+            ship.perform(&Instruction::Right(45)),
+            Err(NavigationError::UnsupportedAngle(135))
+        );
+        assert_eq!(ship.facing, 90);
+
+        let mut waypoint = WaypointShip::new();
+        assert_eq!(
+            waypoint.perform(&Instruction::Left(30)),
+            Err(NavigationError::UnsupportedAngle(-30))
+        );
+    }
+
+    #[test]
+    fn test_arbitrary_angles() {
+        let mut ship = Ship::new().with_angle_mode(AngleMode::Arbitrary);
+        ship.perform(&Instruction::Left(45)).unwrap();
+        ship.perform(&Instruction::Forward(10)).unwrap();
+        assert_eq!(ship.position(), Coordinate { x: 7, y: 7 });
+        ship.perform(&Instruction::Right(105)).unwrap();
+        ship.perform(&Instruction::Forward(100)).unwrap();
+        assert_eq!(ship.position(), Coordinate { x: 57, y: -80 });
+
+        let mut waypoint = WaypointShip::new().with_angle_mode(AngleMode::Arbitrary);
+        waypoint.perform(&Instruction::Left(60)).unwrap();
+        assert_eq!(waypoint.waypoint(), Some(Coordinate { x: 4, y: 9 }));
+    }
+
+    #[test]
+    fn test_repeated_turns_do_not_drift() {
+        let mut waypoint = WaypointShip::new().with_angle_mode(AngleMode::Arbitrary);
+        waypoint.translate(Coordinate { x: 990, y: 99 });
+        for _ in 0..360 {
+            waypoint.perform(&Instruction::Right(1)).unwrap();
+        }
+        assert_eq!(waypoint.waypoint(), Some(Coordinate { x: 1000, y: 100 }));
+
+        let rotated = Coordinate { x: 10, y: 1 }
+            .rotate(-60, AngleMode::Arbitrary)
+            .unwrap();
+        assert_eq!(rotated, Coordinate { x: 4, y: 9 });
+    }
+
+    #[test]
+    fn test_route_statistics() {
+        let instructions = input_generator(
+            "F10
+            N3
+            F7
+            R90
+            F11",
+        );
+        let route = Ship::new().navigate_route(&instructions).unwrap();
+        assert_eq!(
+            route.positions(),
+            vec![
+                Coordinate { x: 0, y: 0 },
+                Coordinate { x: 10, y: 0 },
+                Coordinate { x: 10, y: 3 },
+                Coordinate { x: 17, y: 3 },
+                Coordinate { x: 17, y: 3 },
+                Coordinate { x: 17, y: -8 }
+            ]
+        );
+        assert_eq!(
+            route.bounding_box(),
+            (Coordinate { x: 0, y: -8 }, Coordinate { x: 17, y: 3 })
+        );
+        assert!((route.distance_travelled() - 31.0).abs() < 1e-9);
+        assert_eq!(route.furthest_point(), Coordinate { x: 17, y: -8 });
+        assert!(route.waypoints().iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_route_export() {
+        let instructions = input_generator(
+            "F10
+            R90
+            F2",
+        );
+        let route = WaypointShip::new().navigate_route(&instructions).unwrap();
+        assert_eq!(
+            route.to_csv(),
+            "step,x,y,waypoint_x,waypoint_y\n0,0,0,10,1\n1,100,10,10,1\n2,100,10,1,-10\n3,102,-10,1,-10\n"
+        );
+        let svg = route.to_svg();
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-5 -15 112 30\">")
+        );
+        assert!(svg.contains("points=\"0,0 100,-10 100,-10 102,10\""));
+    }
+
+    #[test]
+    fn test_plan_ship() {
+        let ship = Ship::new();
+        assert_eq!(ship.plan_to(Coordinate { x: 0, y: 0 }), vec![]);
+        assert_eq!(
+            ship.plan_to(Coordinate { x: -4, y: 7 }),
+            vec![Instruction::West(4), Instruction::North(7)]
+        );
+    }
+
+    #[test]
+    fn test_plan_waypoint() {
+        let ship = WaypointShip::new();
+        assert_eq!(
+            ship.plan_to(Coordinate { x: 30, y: 3 }),
+            vec![Instruction::Forward(3)]
+        );
+        assert_eq!(
+            ship.plan_to(Coordinate { x: 2, y: -20 }),
+            vec![Instruction::Right(90), Instruction::Forward(2)]
+        );
+        assert_eq!(
+            ship.plan_to(Coordinate { x: 12, y: 2 }),
+            vec![Instruction::West(4), Instruction::Forward(2)]
+        );
+        assert_eq!(
+            ship.plan_to(Coordinate { x: 7, y: 5 }),
+            vec![
+                Instruction::West(3),
+                Instruction::North(4),
+                Instruction::Forward(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_optimize() {
+        let instructions = input_generator(
+            "F10
+            N3
+            F7
+            R90
+            F11",
+        );
+        assert_eq!(
+            Ship::new().optimize(&instructions).unwrap(),
+            vec![
+                Instruction::East(17),
+                Instruction::South(8),
+                Instruction::Right(90)
+            ]
+        );
+        let waypoint = WaypointShip::new();
+        assert_eq!(waypoint.optimize(&instructions).unwrap(), instructions);
+        let instructions = input_generator(
+            "F10
+            F5
+            R180
+            L180
+            F2",
+        );
+        assert_eq!(
+            waypoint.optimize(&instructions).unwrap(),
+            vec![Instruction::Forward(17)]
+        );
+    }
+
+    fn instruction() -> impl Strategy<Value = Instruction> {
+        prop_oneof![
+            (0i64..20).prop_map(Instruction::North),
+            (0i64..20).prop_map(Instruction::South),
+            (0i64..20).prop_map(Instruction::East),
+            (0i64..20).prop_map(Instruction::West),
+            (1i64..4).prop_map(|d| Instruction::Left(d * 90)),
+            (1i64..4).prop_map(|d| Instruction::Right(d * 90)),
+            (0i64..20).prop_map(Instruction::Forward),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_plan_reaches_target(x in -200i64..200, y in -200i64..200, prefix in prop::collection::vec(instruction(), 0..6)) {
+            let target = Coordinate { x, y };
+            let mut waypoint = WaypointShip::new();
+            waypoint.navigate(&prefix).unwrap();
+            let plan = waypoint.plan_to(target);
+            prop_assert!(plan.len() <= 3);
+            waypoint.navigate(&plan).unwrap();
+            prop_assert_eq!(waypoint.position(), target);
+
+            let mut ship = Ship::new();
+            ship.navigate(&prefix).unwrap();
+            let plan = ship.plan_to(target);
+            prop_assert!(plan.len() <= 2);
+            ship.navigate(&plan).unwrap();
+            prop_assert_eq!(ship.position(), target);
+        }
+
+        #[test]
+        fn prop_optimize_is_equivalent(instructions in prop::collection::vec(instruction(), 0..12)) {
+            let mut expected = WaypointShip::new();
+            expected.navigate(&instructions).unwrap();
+            let optimized = WaypointShip::new().optimize(&instructions).unwrap();
+            prop_assert!(optimized.len() <= instructions.len());
+            let mut actual = WaypointShip::new();
+            actual.navigate(&optimized).unwrap();
+            prop_assert_eq!(actual.position(), expected.position());
+            prop_assert_eq!(actual.waypoint(), expected.waypoint());
+
+            let mut expected = Ship::new();
+            expected.navigate(&instructions).unwrap();
+            let optimized = Ship::new().optimize(&instructions).unwrap();
+            prop_assert!(optimized.len() <= instructions.len());
+            let mut actual = Ship::new();
+            actual.navigate(&optimized).unwrap();
+            prop_assert_eq!(actual.position(), expected.position());
+            prop_assert_eq!(actual.facing, expected.facing);
+        }
     }
 }
@@ -1,3 +1,7 @@
+use crate::number_theory::{crt_with_fallback, gcd, CrtError};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
 #[derive(Eq, PartialEq, Debug)]
 enum Entry {
     Unknown,
@@ -9,11 +13,6 @@ struct Input {
     busses: Vec<Entry>,
 }
 
-struct BezoutIdentity {
-    _s: i64,
-    t: i64,
-}
-
 #[aoc_generator(day13)]
 fn input_generator(input: &str) -> Input {
     let lines: Vec<&str> = input.lines().map(&str::trim).collect();
@@ -33,82 +32,220 @@ fn input_generator(input: &str) -> Input {
     }
 }
 
-#[aoc(day13, part1)]
-fn day13_part1(input: &Input) -> u64 {
-    let busses = input.busses.iter().filter_map(|f| match f {
-        Entry::Bus(t) => Some(t),
-        _ => None,
-    });
+#[derive(Debug, PartialEq, Eq)]
+pub struct Bus {
+    pub id: u64,
+    pub offset: u64,
+}
 
-    let next_departure = busses
-        .into_iter()
-        .map(|b| (b, b * (input.timestamp / b) + b))
-        .min_by_key(|b| b.1)
-        .unwrap();
+#[derive(Debug, PartialEq, Eq)]
+pub struct Departure {
+    pub bus: u64,
+    pub time: u64,
+    pub wait: u64,
+}
 
-    (next_departure.1 - input.timestamp) * next_departure.0
+#[derive(Debug, PartialEq, Eq)]
+pub struct Alignment {
+    pub first: u64,
+    pub period: u64,
 }
 
-#[aoc(day13, part2)]
-fn day13_part2(input: &Input) -> i64 {
-    let busses: Vec<_> = input
-        .busses
-        .iter()
-        .enumerate()
-        .filter_map(|f| match f.1 {
-            Entry::Bus(t) => Some((((*t as i64) - (f.0 as i64)).rem_euclid(*t as i64), *t)),
-            _ => None,
-        })
-        .collect();
+impl Alignment {
+    pub fn timestamps(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..).map(move |k| self.first + k * self.period)
+    }
+}
 
-    let n: Vec<i64> = busses.iter().map(|b| b.1 as i64).collect();
-    let a: Vec<i64> = busses.iter().map(|b| b.0).collect();
-    chinese_remainder_theorem(&n, &a)
+#[derive(Debug, PartialEq, Eq)]
+pub enum ScheduleError {
+    Parse(String),
+    InvalidBus { offset: u64 },
+    Alignment(CrtError),
+    Overflow,
 }
 
-fn extended_euclidean(prod: i64, n: i64) -> BezoutIdentity {
-    let mut t0 = 0;
-    let mut t1 = 1;
-    let mut s0 = 1;
-    let mut s1 = 0;
-    let mut r2 = prod;
-    let mut r1 = n;
+#[derive(Debug, PartialEq, Eq)]
+pub struct Schedule {
+    buses: Vec<Bus>,
+}
 
-    while r1 != 0 {
-        let q1 = r2 / r1;
-        let r2_temp = r2;
-        r2 = r1;
-        r1 = r2_temp - q1 * r1;
+impl Schedule {
+    pub fn new(entries: impl IntoIterator<Item = Option<u64>>) -> Result<Self, ScheduleError> {
+        let buses = entries
+            .into_iter()
+            .enumerate()
+            .filter_map(|(offset, id)| {
+                id.map(|id| Bus {
+                    id,
+                    offset: offset as u64,
+                })
+            })
+            .collect();
+        Self::with_offsets(buses)
+    }
 
-        let t_temp = t0 - q1 * t1;
-        t0 = t1;
-        t1 = t_temp;
+    pub fn with_offsets(buses: Vec<Bus>) -> Result<Self, ScheduleError> {
+        match buses.iter().find(|b| b.id == 0) {
+            Some(bus) => Err(ScheduleError::InvalidBus { offset: bus.offset }),
+            None => Ok(Schedule { buses }),
+        }
+    }
 
-        let s_temp = s0 - q1 * s1;
-        s0 = s1;
-        s1 = s_temp;
+    pub fn buses(&self) -> &[Bus] {
+        &self.buses
     }
 
-    BezoutIdentity { _s: s0, t: t0 }
-}
+    pub fn subset(&self, ids: &[u64]) -> Schedule {
+        let buses = self
+            .buses
+            .iter()
+            .filter(|b| ids.contains(&b.id))
+            .map(|b| Bus {
+                id: b.id,
+                offset: b.offset,
+            })
+            .collect();
+        Schedule { buses }
+    }
 
-fn chinese_remainder_theorem(n: &[i64], a: &[i64]) -> i64 {
-    let prod: i64 = n.iter().product();
-    n.iter()
-        .zip(a)
-        .map(|(n, a)| {
-            let p = prod / *n;
-            let b = extended_euclidean(*n, p);
-            let res = p * b.t * a;
-            res.rem_euclid(prod)
+    // Departures are strictly after t, so a bus leaving exactly at t is caught on its next round.
+    pub fn next_departures(&self, t: u64) -> Vec<Departure> {
+        let mut departures: Vec<Departure> = self
+            .buses
+            .iter()
+            .map(|b| {
+                let time = b.id * (t / b.id) + b.id;
+                Departure {
+                    bus: b.id,
+                    time,
+                    wait: time - t,
+                }
+            })
+            .collect();
+        departures.sort_by_key(|d| (d.time, d.bus));
+        departures
+    }
+
+    pub fn earliest_departure(&self, t: u64) -> Option<Departure> {
+        self.next_departures(t).into_iter().next()
+    }
+
+    pub fn alignment(&self) -> Result<Alignment, ScheduleError> {
+        let congruences: Vec<(i128, i128)> = self
+            .buses
+            .iter()
+            .map(|b| (-(b.offset as i128), b.id as i128))
+            .collect();
+        let res = crt_with_fallback(&congruences).map_err(ScheduleError::Alignment)?;
+        Ok(Alignment {
+            first: u64::try_from(&res.residue).map_err(|_| ScheduleError::Overflow)?,
+            period: u64::try_from(&res.modulus).map_err(|_| ScheduleError::Overflow)?,
         })
-        .sum::<i64>()
-        .rem_euclid(prod)
+    }
+
+    pub fn sieve_alignment(&self) -> Result<Alignment, ScheduleError> {
+        let mut first = 0u64;
+        let mut period = 1u64;
+        for (i, bus) in self.buses.iter().enumerate() {
+            let shared = gcd(period as i128, bus.id as i128) as u64;
+            let attempts = bus.id / shared;
+            let offset = bus.offset % bus.id;
+            let mut found = false;
+            for _ in 0..attempts {
+                if (first % bus.id + offset).is_multiple_of(bus.id) {
+                    found = true;
+                    break;
+                }
+                first = first.checked_add(period).ok_or(ScheduleError::Overflow)?;
+            }
+            if !found {
+                return Err(ScheduleError::Alignment(CrtError::Inconsistent(i)));
+            }
+            period = period
+                .checked_mul(attempts)
+                .ok_or(ScheduleError::Overflow)?;
+        }
+
+        Ok(Alignment { first, period })
+    }
+
+    pub fn is_pairwise_coprime(&self) -> bool {
+        self.buses.iter().enumerate().all(|(i, a)| {
+            self.buses[i + 1..]
+                .iter()
+                .all(|b| gcd(a.id as i128, b.id as i128) == 1)
+        })
+    }
+
+    pub fn solve(&self) -> Result<Alignment, ScheduleError> {
+        if self.is_pairwise_coprime() {
+            self.alignment()
+        } else {
+            self.sieve_alignment()
+        }
+    }
+
+    pub fn earliest_aligned(&self, k: usize) -> Result<Vec<u64>, ScheduleError> {
+        let alignment = self.alignment()?;
+        Ok(alignment.timestamps().take(k).collect())
+    }
+
+    pub fn period(&self) -> Result<u64, ScheduleError> {
+        self.alignment().map(|a| a.period)
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .split(',')
+            .map(|b| match b.trim() {
+                "x" => Ok(None),
+                b => match b.parse() {
+                    Ok(id) => Ok(Some(id)),
+                    Err(_) => Err(ScheduleError::Parse(b.to_owned())),
+                },
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(Schedule::new)
+    }
+}
+
+fn schedule(input: &Input) -> Schedule {
+    Schedule::new(input.busses.iter().map(|b| match b {
+        Entry::Bus(t) => Some(*t),
+        Entry::Unknown => None,
+    }))
+    .unwrap_or_else(|e| panic!("Invalid schedule: {:?}", e))
+}
+
+#[aoc(day13, part1)]
+fn day13_part1(input: &Input) -> u64 {
+    let departure = schedule(input).earliest_departure(input.timestamp).unwrap();
+    departure.wait * departure.bus
+}
+
+#[aoc(day13, part2)]
+fn day13_part2(input: &Input) -> i64 {
+    schedule(input).solve().unwrap().first as i64
+}
+
+#[aoc(day13, part2, sieve)]
+fn day13_part2_sieve(input: &Input) -> i64 {
+    schedule(input).sieve_alignment().unwrap().first as i64
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{chinese_remainder_theorem, day13_part1, day13_part2, input_generator};
+    use super::{
+        day13_part1, day13_part2, day13_part2_sieve, input_generator, Alignment, Bus, Departure,
+        Schedule, ScheduleError,
+    };
+    use crate::number_theory::CrtError;
+    use proptest::prelude::*;
 
     #[test]
     fn test_given_part_1() {
@@ -119,6 +256,14 @@ mod tests {
         assert_eq!(res, 295);
     }
 
+    #[test]
+    fn test_part_1_timestamp_multiple_of_bus() {
+        let input = "14
+        7,13";
+        let generated = input_generator(input);
+        assert_eq!(day13_part1(&generated), 49);
+    }
+
     #[test]
     fn test_given_part_2_sample_1() {
         let input = "939
@@ -181,31 +326,128 @@ mod tests {
     }
 
     #[test]
-    fn test_theorem() {
-        let res = chinese_remainder_theorem(&[3, 5, 7], &[2, 3, 2]);
-        assert_eq!(res, 23);
+    fn test_schedule_next_departures() {
+        let schedule: Schedule = "7,13,x,x,59,x,31,19".parse().unwrap();
+        let departures = schedule.next_departures(939);
+        assert_eq!(
+            departures[0],
+            Departure {
+                bus: 59,
+                time: 944,
+                wait: 5
+            }
+        );
+        assert_eq!(departures.len(), 5);
+        assert!(departures.windows(2).all(|w| w[0].time <= w[1].time));
+        assert_eq!(schedule.next_departures(0)[0].wait, 7);
     }
 
     #[test]
-    fn test_theorem_2() {
-        let res = chinese_remainder_theorem(&[5, 7, 12], &[0, 6, 10]);
-        assert_eq!(res, 370);
+    fn test_schedule_alignment() {
+        let schedule: Schedule = "17,x,13,19".parse().unwrap();
+        assert_eq!(
+            schedule.alignment(),
+            Ok(Alignment {
+                first: 3417,
+                period: 17 * 13 * 19
+            })
+        );
+        assert_eq!(
+            schedule.earliest_aligned(3),
+            Ok(vec![3417, 3417 + 4199, 3417 + 2 * 4199])
+        );
+        assert_eq!(schedule.period(), Ok(4199));
     }
 
     #[test]
-    fn test_theorem_3() {
-        let res = chinese_remainder_theorem(&[13, 5, 7], &[2, 3, 2]);
-        assert_eq!(res, 93);
+    fn test_schedule_subset_and_offsets() {
+        let schedule: Schedule = "7,13,x,x,59,x,31,19".parse().unwrap();
+        let subset = schedule.subset(&[7, 13]);
+        assert_eq!(subset.alignment().unwrap().first, 77);
+
+        let custom =
+            Schedule::with_offsets(vec![Bus { id: 4, offset: 0 }, Bus { id: 6, offset: 1 }])
+                .unwrap();
+        assert_eq!(
+            custom.alignment(),
+            Err(ScheduleError::Alignment(CrtError::Inconsistent(1)))
+        );
+        let custom =
+            Schedule::with_offsets(vec![Bus { id: 4, offset: 0 }, Bus { id: 6, offset: 2 }])
+                .unwrap();
+        assert_eq!(
+            custom.alignment(),
+            Ok(Alignment {
+                first: 4,
+                period: 12
+            })
+        );
     }
 
     #[test]
-    fn test_theorem_4() {
-        let res = chinese_remainder_theorem(&[13, 5, 29], &[2, 5, 7]);
-        assert_eq!(res, 210);
+    fn test_schedule_alignment_overflow() {
+        let schedule: Schedule = "1000000000000000003,1000000000000000009,1000000000000000031"
+            .parse()
+            .unwrap();
+        assert_eq!(schedule.alignment(), Err(ScheduleError::Overflow));
     }
+
     #[test]
-    fn test_theorem_5() {
-        let res = chinese_remainder_theorem(&[13, 5, 29], &[24, 5, 7]);
-        assert_eq!(res, 1805);
+    fn test_schedule_parse_error() {
+        assert_eq!(
+            "7,y".parse::<Schedule>(),
+            Err(ScheduleError::Parse("y".to_owned()))
+        );
+        assert_eq!(
+            "3,x,0".parse::<Schedule>(),
+            Err(ScheduleError::InvalidBus { offset: 2 })
+        );
+        assert_eq!(
+            Schedule::new(vec![Some(0), Some(3)]),
+            Err(ScheduleError::InvalidBus { offset: 0 })
+        );
+        assert!(Schedule::with_offsets(vec![Bus { id: 0, offset: 5 }]).is_err());
+    }
+
+    #[test]
+    fn test_sieve_given() {
+        let input = "939
+        7,13,x,x,59,x,31,19";
+        let generated = input_generator(input);
+        assert_eq!(day13_part2_sieve(&generated), 1068781);
+    }
+
+    #[test]
+    fn test_solve_shared_factors() {
+        let schedule: Schedule = "4,x,6,x,x,x,x,x,x,x,x,9".parse().unwrap();
+        assert!(!schedule.is_pairwise_coprime());
+        assert_eq!(schedule.solve(), schedule.alignment());
+        let alignment = schedule.solve().unwrap();
+        assert_eq!(
+            alignment,
+            Alignment {
+                first: 16,
+                period: 36
+            }
+        );
+
+        let schedule: Schedule = "4,6,x,8".parse().unwrap();
+        assert_eq!(
+            schedule.sieve_alignment(),
+            Err(ScheduleError::Alignment(CrtError::Inconsistent(1)))
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_sieve_matches_crt(entries in prop::collection::vec(prop::option::of(1u64..40), 1..7)) {
+            let schedule = Schedule::new(entries).unwrap();
+            let crt = schedule.alignment();
+            let sieve = schedule.sieve_alignment();
+            prop_assert_eq!(crt.is_ok(), sieve.is_ok());
+            if let (Ok(crt), Ok(sieve)) = (crt, sieve) {
+                prop_assert_eq!(crt, sieve);
+            }
+        }
     }
 }
@@ -1,29 +1,11 @@
+use crate::masked_memory::{Decoder, Mask, MaskedMemory, MemoryDiff};
 use regex::Regex;
-use std::collections::HashMap;
-#[derive(Debug)]
-enum Operation {
-    SetMask { mask: String },
-    SetMem { adress: usize, value: u64 },
-}
+use std::convert::TryFrom;
 
 #[derive(Debug)]
-struct Mask {
-    set: u64,
-    unset: u64,
-}
-
-fn to_mask(mask: &str) -> Mask {
-    let keep: u64 = 0b111111111111111111111111111111111111;
-    let set: String = mask.replace("X", "0");
-    let unset: String = mask.replace("X", "1");
-
-    let set = u64::from_str_radix(&set, 2).unwrap();
-    let unset = u64::from_str_radix(&unset, 2).unwrap();
-
-    Mask {
-        set,
-        unset: unset & keep,
-    }
+enum Operation {
+    SetMask { mask: Mask },
+    SetMem { adress: u64, value: u64 },
 }
 
 #[aoc_generator(day14)]
@@ -43,89 +25,58 @@ fn input_generator(input: &str) -> Vec<Operation> {
                 }
             } else {
                 Operation::SetMask {
-                    mask: value.to_owned(),
+                    mask: value.parse().unwrap(),
                 }
             }
         })
         .collect()
 }
 
-#[aoc(day14, part1)]
-fn day14_part1(input: &[Operation]) -> u64 {
-    let mut current_mask = Mask { set: 0, unset: 0 };
-    let mut stack: HashMap<usize, u64> = HashMap::new();
+fn run(input: &[Operation], decoder: Decoder) -> MaskedMemory {
+    let mut memory = MaskedMemory::new(decoder, 36).unwrap();
     for op in input {
         match op {
-            Operation::SetMask { mask } => current_mask = to_mask(&mask),
-            Operation::SetMem { adress, value } => {
-                stack.insert(*adress, (value | current_mask.set) & current_mask.unset);
-            }
+            Operation::SetMask { mask } => memory.set_mask(*mask).unwrap(),
+            Operation::SetMem { adress, value } => memory.write(*adress, *value),
         }
     }
 
-    stack.values().sum()
+    memory
 }
 
-fn replace_x(indices: &[usize], adress: &str) -> Vec<String> {
-    if indices.is_empty() {
-        return vec![adress.to_owned()];
-    }
-    let index = indices[0];
-
-    let mut one_replacement = adress.to_owned();
-    let mut zero_replacement = one_replacement.clone();
-    one_replacement.replace_range(index..(index + 1), "1");
-    zero_replacement.replace_range(index..(index + 1), "0");
-    let one_branch = replace_x(&indices[1..], &one_replacement);
-    let zero_branch = replace_x(&indices[1..], &zero_replacement);
-
-    one_branch
-        .into_iter()
-        .chain(zero_branch.into_iter())
-        .collect()
+pub fn execute(program: &str, decoder: Decoder) -> MaskedMemory {
+    run(&input_generator(program), decoder)
 }
 
-fn set_at_adress(stack: &mut HashMap<usize, u64>, mask: &str, adress: usize, value: u64) {
-    let adress = adress | usize::from_str_radix(&mask.replace('X', "0"), 2).unwrap();
-    let adress = format!("{:036b}", adress);
-    let indices: Vec<usize> = mask.match_indices('X').map(|c| c.0).collect();
-
-    let adresses = replace_x(&indices, &adress)
-        .into_iter()
-        .map(|a| usize::from_str_radix(&a, 2).unwrap());
+pub fn decoder_diff(program: &str) -> Vec<MemoryDiff> {
+    let input = input_generator(program);
+    run(&input, Decoder::V1).diff(&run(&input, Decoder::V2))
+}
 
-    for adress in adresses {
-        stack.insert(adress, value);
-    }
+#[aoc(day14, part1)]
+fn day14_part1(input: &[Operation]) -> u64 {
+    u64::try_from(run(input, Decoder::V1).sum()).unwrap()
 }
 
 #[aoc(day14, part2)]
 fn day14_part2(input: &[Operation]) -> u64 {
-    let mut current_mask = String::new();
-
-    let mut stack: HashMap<usize, u64> = HashMap::new();
-    for op in input {
-        match op {
-            Operation::SetMask { mask } => current_mask = mask.clone(),
-            Operation::SetMem { adress, value } => {
-                set_at_adress(&mut stack, &current_mask, *adress, *value)
-            }
-        }
-    }
-
-    stack.values().sum()
+    u64::try_from(run(input, Decoder::V2).sum()).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{day14_part1, day14_part2, input_generator, to_mask};
+    use super::{day14_part1, day14_part2, decoder_diff, execute, input_generator};
+    use crate::masked_memory::{Decoder, Mask};
 
     #[test]
     fn test_parse_mask() {
         let input = "XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X";
-        let mask = to_mask(&input);
-        assert_eq!(mask.set, 0b1000000);
-        assert_eq!(mask.unset, 0b111111111111111111111111111111111101);
+        let mask: Mask = input.parse().unwrap();
+        assert_eq!(mask.ones, 0b1000000);
+        assert_eq!(
+            !mask.zeros & mask.width_mask(),
+            0b111111111111111111111111111111111101
+        );
     }
 
     #[test]
@@ -150,4 +101,34 @@ mod tests {
         let res = day14_part2(&generated);
         assert_eq!(res, 208);
     }
+
+    #[test]
+    fn test_execute_and_diff() {
+        let input = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+            mem[8] = 11
+            mem[7] = 101
+            mem[8] = 0";
+        let memory = execute(input, Decoder::V1);
+        assert_eq!(memory.dump_addresses(2), Ok(vec![(7, 101), (8, 64)]));
+        let touched: Vec<u64> = memory.log().iter().map(|w| w.address).collect();
+        assert_eq!(touched, vec![8, 7, 8]);
+
+        let input = "mask = 000000000000000000000000000000X1001X
+            mem[42] = 100
+            mask = 00000000000000000000000000000000X0XX
+            mem[26] = 1";
+        assert_eq!(
+            execute(input, Decoder::V2)
+                .dump_addresses(10)
+                .unwrap()
+                .len(),
+            10
+        );
+        let diff = decoder_diff(input);
+        assert_eq!(diff.iter().map(|d| d.pattern.count()).sum::<u128>(), 10);
+        assert_eq!(diff[0].pattern.bits, 16);
+        assert!(diff
+            .iter()
+            .all(|d| d.left != d.right && !d.pattern.contains(26)));
+    }
 }
@@ -1,6 +1,96 @@
 use std::collections::HashMap;
+const DEFAULT_DENSE_LIMIT: u32 = 1 << 22;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GameStats {
+    pub turns: u32,
+    pub distinct: usize,
+    pub largest_gap: u32,
+}
+
+pub struct MemoryGame {
+    starting: Vec<u32>,
+    turn: u32,
+    next: u32,
+    dense: Vec<u32>,
+    sparse: HashMap<u32, u32>,
+    dense_limit: u32,
+    distinct: usize,
+    largest_gap: u32,
+}
+
+impl MemoryGame {
+    pub fn new(starting: &[u32]) -> Self {
+        Self::with_dense_limit(starting, DEFAULT_DENSE_LIMIT)
+    }
+
+    pub fn with_dense_limit(starting: &[u32], dense_limit: u32) -> Self {
+        MemoryGame {
+            starting: starting.to_vec(),
+            turn: 0,
+            next: 0,
+            dense: Vec::new(),
+            sparse: HashMap::new(),
+            dense_limit,
+            distinct: 0,
+            largest_gap: 0,
+        }
+    }
+
+    pub fn stats(&self) -> GameStats {
+        GameStats {
+            turns: self.turn,
+            distinct: self.distinct,
+            largest_gap: self.largest_gap,
+        }
+    }
+
+    // Stores `turn` as the latest turn `value` was spoken and returns the previous one.
+    fn record(&mut self, value: u32, turn: u32) -> Option<u32> {
+        let previous = if value < self.dense_limit {
+            let index = value as usize;
+            if index >= self.dense.len() {
+                let len = (index + 1).max(self.dense.len() * 2);
+                self.dense.resize(len.min(self.dense_limit as usize), 0);
+            }
+            std::mem::replace(&mut self.dense[index], turn)
+        } else {
+            self.sparse.insert(value, turn).unwrap_or(0)
+        };
+
+        Some(previous).filter(|p| *p != 0)
+    }
+}
+
+impl Iterator for MemoryGame {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let value = self
+            .starting
+            .get(self.turn as usize)
+            .copied()
+            .unwrap_or(self.next);
+        self.turn = self.turn.checked_add(1)?;
+
+        match self.record(value, self.turn) {
+            Some(previous) => {
+                let gap = self.turn - previous;
+                self.largest_gap = self.largest_gap.max(gap);
+                self.next = gap;
+            }
+            None => {
+                self.distinct += 1;
+                self.next = 0;
+            }
+        }
+
+        Some(value)
+    }
+}
+
 #[aoc_generator(day15)]
-fn input_generator(input: &str) -> Vec<u64> {
+fn input_generator(input: &str) -> Vec<u32> {
     input
         .split(',')
         .map(str::parse)
@@ -36,48 +126,22 @@ fn _find_nth_number(input: &[u64], n: usize) -> u64 {
     spoken as u64
 }
 
-fn find_nth_array(input: &[u64], n: usize) -> u64 {
-    let mut numbers = Vec::<usize>::with_capacity(n);
-    let mut spoken = 0;
-    numbers.resize(n, 0);
-
-    for (index, value) in input.iter().enumerate() {
-        let last_occurence = numbers[*value as usize];
-        if last_occurence != 0 {
-            spoken = index - last_occurence;
-        } else {
-            spoken = 0;
-        }
-        numbers[*value as usize] = index + 1;
-    }
-
-    for index in (input.len() + 1)..n {
-        let last_occurence = numbers[spoken];
-        let t = spoken;
-        if last_occurence != 0 {
-            spoken = index - last_occurence;
-        } else {
-            spoken = 0;
-        }
-
-        numbers[t] = index;
-    }
-
-    spoken as u64
+fn find_nth_array(input: &[u32], n: usize) -> u32 {
+    MemoryGame::new(input).nth(n - 1).unwrap()
 }
 
 #[aoc(day15, part1)]
-fn day15_part1(input: &[u64]) -> u64 {
+fn day15_part1(input: &[u32]) -> u32 {
     find_nth_array(input, 2020)
 }
 #[aoc(day15, part2)]
-fn day15_part2(input: &[u64]) -> u64 {
+fn day15_part2(input: &[u32]) -> u32 {
     find_nth_array(input, 30000000)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{_find_nth_number, find_nth_array};
+    use super::{_find_nth_number, find_nth_array, GameStats, MemoryGame};
 
     #[test]
     fn test_given_day_15() {
@@ -96,4 +160,34 @@ mod tests {
         let res = _find_nth_number(&[0, 1, 4, 13, 15, 12, 16], 2020);
         assert_eq!(res, 1665);
     }
+
+    #[test]
+    fn test_memory_game_sequence() {
+        let spoken: Vec<u32> = MemoryGame::new(&[0, 3, 6]).take(10).collect();
+        assert_eq!(spoken, vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+    }
+
+    #[test]
+    fn test_memory_game_sparse_matches_dense() {
+        let dense: Vec<u32> = MemoryGame::new(&[2, 1, 3]).take(2020).collect();
+        let sparse: Vec<u32> = MemoryGame::with_dense_limit(&[2, 1, 3], 4)
+            .take(2020)
+            .collect();
+        assert_eq!(dense, sparse);
+        assert_eq!(dense[2019], 10);
+    }
+
+    #[test]
+    fn test_memory_game_stats() {
+        let mut game = MemoryGame::new(&[0, 3, 6]);
+        game.by_ref().take(10).for_each(drop);
+        assert_eq!(
+            game.stats(),
+            GameStats {
+                turns: 10,
+                distinct: 5,
+                largest_gap: 4
+            }
+        );
+    }
 }
@@ -1,5 +1,6 @@
 use lazy_static::lazy_static;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
 
 use regex::Regex;
 
@@ -107,11 +108,104 @@ pub fn day7_part2(input: &Vec<String>) -> u32 {
     count_contained(&"shiny gold".to_owned(), &rules)
 }
 
+#[derive(Default)]
+pub struct DotOptions {
+    pub highlight: Option<String>,
+    pub root: Option<String>,
+}
+
+fn reachable<'a>(start: &'a str, next: impl Fn(&'a str) -> Vec<&'a str>) -> BTreeSet<&'a str> {
+    let mut seen = BTreeSet::new();
+    let mut to_visit = next(start);
+    while let Some(bag) = to_visit.pop() {
+        if seen.insert(bag) {
+            to_visit.extend(next(bag));
+        }
+    }
+    seen
+}
+
+fn descendants<'a>(bag: &'a str, rules: &'a HashMap<String, HashSet<Rule>>) -> BTreeSet<&'a str> {
+    reachable(bag, |b| {
+        rules
+            .get(b)
+            .map(|r| r.iter().map(|r| r.bag_name.as_str()).collect())
+            .unwrap_or_default()
+    })
+}
+
+fn ancestors<'a>(bag: &'a str, rules: &'a HashMap<String, HashSet<Rule>>) -> BTreeSet<&'a str> {
+    reachable(bag, |b| {
+        rules
+            .iter()
+            .filter(|(_, r)| r.iter().any(|r| r.bag_name == b))
+            .map(|(c, _)| c.as_str())
+            .collect()
+    })
+}
+
+pub fn to_dot(rules: &HashMap<String, HashSet<Rule>>, options: &DotOptions) -> String {
+    let mut nodes: BTreeSet<&str> = rules
+        .iter()
+        .flat_map(|(c, r)| std::iter::once(c.as_str()).chain(r.iter().map(|r| r.bag_name.as_str())))
+        .collect();
+
+    if let Some(root) = &options.root {
+        let mut subgraph = descendants(root, rules);
+        subgraph.insert(root);
+        nodes = &nodes & &subgraph;
+    }
+
+    let (above, below) = options
+        .highlight
+        .as_ref()
+        .map(|h| (ancestors(h, rules), descendants(h, rules)))
+        .unwrap_or_default();
+
+    let mut dot = String::from("digraph bags {\n");
+    for node in nodes.iter() {
+        let color = match node {
+            _ if options.highlight.as_deref() == Some(*node) => Some("gold"),
+            _ if above.contains(node) => Some("lightblue"),
+            _ if below.contains(node) => Some("lightgreen"),
+            _ => None,
+        };
+        match color {
+            Some(c) => writeln!(dot, "    \"{}\" [style=filled, fillcolor={}];", node, c),
+            None => writeln!(dot, "    \"{}\";", node),
+        }
+        .unwrap();
+    }
+
+    for container in nodes.iter() {
+        let mut contained: Vec<_> = rules
+            .get(*container)
+            .map(|r| {
+                r.iter()
+                    .filter(|r| nodes.contains(r.bag_name.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        contained.sort_by(|a: &&Rule, b| a.bag_name.cmp(&b.bag_name));
+        for rule in contained {
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                container, rule.bag_name, rule.count
+            )
+            .unwrap();
+        }
+    }
+    dot.push_str("}\n");
+
+    dot
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::{HashMap, HashSet};
 
-    use super::{day7_part1, day7_part2, parse_rule, parse_rule_part_2, Rule};
+    use super::{day7_part1, day7_part2, parse_rule, parse_rule_part_2, to_dot, DotOptions, Rule};
 
     #[test]
     fn test_parse_rule_basic() {
@@ -220,4 +314,43 @@ mod tests {
         let res = day7_part2(&input);
         assert_eq!(res, 6);
     }
+
+    fn dot_rules() -> HashMap<String, HashSet<Rule>> {
+        vec![
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.",
+            "bright white bags contain 1 shiny gold bag.",
+            "muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.",
+            "shiny gold bags contain 1 dark olive bag.",
+            "dark olive bags contain no other bags.",
+            "faded blue bags contain no other bags.",
+        ]
+        .into_iter()
+        .map(|s| parse_rule_part_2(&s.to_owned()))
+        .collect()
+    }
+
+    #[test]
+    fn test_to_dot_edges() {
+        let dot = to_dot(&dot_rules(), &DotOptions::default());
+        assert!(dot.starts_with("digraph bags {"));
+        assert!(dot.contains("\"muted yellow\" -> \"faded blue\" [label=\"9\"];"));
+        assert!(dot.contains("\"light red\" -> \"bright white\" [label=\"1\"];"));
+        assert_eq!(dot.matches("->").count(), 6);
+    }
+
+    #[test]
+    fn test_to_dot_highlight_and_root() {
+        let options = DotOptions {
+            highlight: Some("shiny gold".to_owned()),
+            root: Some("muted yellow".to_owned()),
+        };
+        let dot = to_dot(&dot_rules(), &options);
+        assert!(!dot.contains("light red"));
+        assert!(!dot.contains("bright white"));
+        assert!(dot.contains("\"shiny gold\" [style=filled, fillcolor=gold];"));
+        assert!(dot.contains("\"muted yellow\" [style=filled, fillcolor=lightblue];"));
+        assert!(dot.contains("\"dark olive\" [style=filled, fillcolor=lightgreen];"));
+        assert!(dot.contains("\"faded blue\";"));
+        assert_eq!(dot.matches("->").count(), 3);
+    }
 }
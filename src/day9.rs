@@ -1,4 +1,8 @@
-use std::collections::BTreeSet;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead};
+use std::ops::Range;
+
+const DEFAULT_PREAMBLE_LENGTH: usize = 25;
 
 #[aoc_generator(day9)]
 fn input_generator(input: &str) -> Vec<u64> {
@@ -10,78 +14,213 @@ fn input_generator(input: &str) -> Vec<u64> {
         .collect()
 }
 
-fn calc_valid_numbers(input: &Vec<u64>, start: usize, preamble_length: usize) -> BTreeSet<u64> {
-    let mut valid_numbers: BTreeSet<u64> = BTreeSet::new();
-    for current in start..(preamble_length + start) {
-        let mut it = input.iter().skip(current).take(preamble_length);
-        while let Some(c) = it.next() {
-            for i in it.clone() {
-                valid_numbers.insert(c + *i);
+pub struct XmasWindow {
+    preamble_length: usize,
+    window: VecDeque<u64>,
+    sums: HashMap<u64, usize>,
+}
+
+impl XmasWindow {
+    pub fn new(preamble_length: usize) -> Self {
+        Self {
+            preamble_length,
+            window: VecDeque::with_capacity(preamble_length),
+            sums: HashMap::new(),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.window.len() == self.preamble_length
+    }
+
+    pub fn is_valid(&self, number: u64) -> bool {
+        self.sums.contains_key(&number)
+    }
+
+    pub fn push(&mut self, number: u64) {
+        if self.is_full() {
+            if let Some(oldest) = self.window.pop_front() {
+                for sum in self.window.iter().filter_map(|o| oldest.checked_add(*o)) {
+                    let count = self.sums.get_mut(&sum).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        self.sums.remove(&sum);
+                    }
+                }
             }
         }
+
+        for sum in self.window.iter().filter_map(|o| number.checked_add(*o)) {
+            *self.sums.entry(sum).or_insert(0) += 1;
+        }
+        if self.preamble_length > 0 {
+            self.window.push_back(number);
+        }
     }
+}
 
-    valid_numbers
+pub struct XmasStream {
+    window: XmasWindow,
+    position: usize,
 }
 
-fn find_invalid_number(input: &Vec<u64>, preamble_length: usize) -> u64 {
-    input
-        .iter()
-        .skip(preamble_length)
-        .enumerate()
-        .filter(|i| !calc_valid_numbers(input, i.0, preamble_length).contains(i.1))
+impl XmasStream {
+    pub fn new(preamble_length: usize) -> Self {
+        Self {
+            window: XmasWindow::new(preamble_length),
+            position: 0,
+        }
+    }
+
+    pub fn feed(&mut self, number: u64) -> Option<(usize, u64)> {
+        let position = self.position;
+        let invalid = self.window.is_full() && !self.window.is_valid(number);
+        self.window.push(number);
+        self.position += 1;
+
+        Some((position, number)).filter(|_| invalid)
+    }
+}
+
+pub fn validate_stream<I: IntoIterator<Item = u64>>(
+    numbers: I,
+    preamble_length: usize,
+) -> impl Iterator<Item = (usize, u64)> {
+    let mut stream = XmasStream::new(preamble_length);
+    numbers.into_iter().filter_map(move |n| stream.feed(n))
+}
+
+pub fn validate_reader<R: BufRead>(
+    reader: R,
+    preamble_length: usize,
+) -> impl Iterator<Item = io::Result<(usize, u64)>> {
+    let mut stream = XmasStream::new(preamble_length);
+    reader
+        .lines()
+        .filter(|l| l.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|l| {
+            l.and_then(|l| {
+                l.trim()
+                    .parse::<u64>()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            })
+        })
+        .filter_map(move |n| match n {
+            Ok(n) => stream.feed(n).map(Ok),
+            Err(e) => Some(Err(e)),
+        })
+}
+
+fn find_invalid_number(input: &[u64], preamble_length: usize) -> u64 {
+    validate_stream(input.iter().copied(), preamble_length)
         .next()
-        .map(|s| *s.1)
-        .unwrap()
+        .map(|i| i.1)
+        .expect("Found no invalid number")
 }
 
 #[aoc(day9, part1, iterators)]
-fn day9_part1(input: &Vec<u64>) -> u64 {
-    find_invalid_number(input, 25)
+fn day9_part1(input: &[u64]) -> u64 {
+    find_invalid_number(input, DEFAULT_PREAMBLE_LENGTH)
 }
 
-fn find_encryption_weakness(input: &Vec<u64>, preamble_length: usize) -> u64 {
-    let target = find_invalid_number(input, preamble_length);
-
-    let mut start = 0usize;
-    let mut range = start + 1;
-
-    loop {
-        let range_iter = input.iter().skip(start).take(range);
-        let sum: u64 = range_iter.clone().sum();
-        if sum < target {
-            range += 1;
-        } else if sum > target {
-            start += 1;
-            range = 1;
-        } else {
-            let smallest = range_iter.clone().min().unwrap();
-            let largest = range_iter.max().unwrap();
-            return smallest + largest;
+// Prefix sums are kept in i128 so that no sum of 64-bit inputs can overflow.
+pub fn contiguous_ranges<T: Copy + Into<i128>>(input: &[T], target: T) -> Vec<Range<usize>> {
+    let target: i128 = target.into();
+    let mut prefix = Vec::with_capacity(input.len() + 1);
+    prefix.push(0i128);
+    for n in input {
+        prefix.push(prefix.last().unwrap() + (*n).into());
+    }
+
+    let mut seen: HashMap<i128, Vec<usize>> = HashMap::new();
+    let mut ranges = Vec::new();
+    for end in 2..prefix.len() {
+        seen.entry(prefix[end - 2]).or_default().push(end - 2);
+        if let Some(starts) = seen.get(&(prefix[end] - target)) {
+            ranges.extend(starts.iter().map(|start| *start..end));
         }
     }
+    ranges.sort_by_key(|r| (r.start, r.end));
+
+    ranges
+}
+
+fn find_encryption_weakness(input: &[u64], preamble_length: usize) -> Option<u64> {
+    let (_, target) = validate_stream(input.iter().copied(), preamble_length).next()?;
+
+    contiguous_ranges(input, target)
+        .into_iter()
+        .next()
+        .and_then(|range| {
+            let smallest = input[range.clone()].iter().min().unwrap();
+            let largest = input[range].iter().max().unwrap();
+            smallest.checked_add(*largest)
+        })
 }
 
 #[aoc(day9, part2)]
-fn day9_part2(input: &Vec<u64>) -> u64 {
-    find_encryption_weakness(input, 25)
+fn day9_part2(input: &[u64]) -> u64 {
+    find_encryption_weakness(input, DEFAULT_PREAMBLE_LENGTH).expect("Found no encryption weakness")
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        calc_valid_numbers, find_encryption_weakness, find_invalid_number, input_generator,
+        contiguous_ranges, find_encryption_weakness, find_invalid_number, input_generator,
+        validate_reader, validate_stream, XmasStream, XmasWindow,
     };
 
     #[test]
-    fn test_calc_valid_numbers() {
-        let input = vec![1, 2, 3, 4, 5];
-        let res = calc_valid_numbers(&input, 0, 5);
-        assert!(res.contains(&3));
-        assert!(!res.contains(&2));
-        assert!(!res.contains(&1));
-        assert!(!res.contains(&10));
-        assert!(res.contains(&9));
+    fn test_window_valid_numbers() {
+        let mut window = XmasWindow::new(5);
+        [1, 2, 3, 4, 5].iter().for_each(|n| window.push(*n));
+        assert!(window.is_valid(3));
+        assert!(!window.is_valid(2));
+        assert!(!window.is_valid(1));
+        assert!(!window.is_valid(10));
+        assert!(window.is_valid(9));
+    }
+
+    #[test]
+    fn test_window_slides() {
+        let mut window = XmasWindow::new(3);
+        [1, 2, 3, 10].iter().for_each(|n| window.push(*n));
+        assert!(!window.is_valid(3));
+        assert!(!window.is_valid(4));
+        assert!(window.is_valid(5));
+        assert!(window.is_valid(13));
+    }
+
+    #[test]
+    fn test_all_invalid_numbers() {
+        let input = vec![1, 2, 3, 10, 5, 20, 15];
+        let res: Vec<_> = validate_stream(input, 2).collect();
+        assert_eq!(res, vec![(3, 10), (4, 5), (5, 20), (6, 15)]);
+    }
+
+    #[test]
+    fn test_stream_feed() {
+        let mut stream = XmasStream::new(2);
+        assert_eq!(stream.feed(1), None);
+        assert_eq!(stream.feed(2), None);
+        assert_eq!(stream.feed(3), None);
+        assert_eq!(stream.feed(4), Some((3, 4)));
+        assert_eq!(stream.feed(7), None);
+    }
+
+    #[test]
+    fn test_validate_reader() {
+        let input = "1\n2\n\n3\n10\nfoo\n13\n";
+        let res: Vec<_> = validate_reader(input.as_bytes(), 2).collect();
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].as_ref().unwrap(), &(3, 10));
+        assert!(res[1].is_err());
+    }
+
+    #[test]
+    fn test_validate_unbounded() {
+        let res: Vec<_> = validate_stream((1..).map(|n| n * n), 3).take(3).collect();
+        assert_eq!(res, vec![(3, 16), (5, 36), (6, 49)]);
     }
 
     #[test]
@@ -135,6 +274,37 @@ mod tests {
         576";
         let input: Vec<u64> = input_generator(numbers);
         let res = find_encryption_weakness(&input, 5);
-        assert_eq!(res, 62);
+        assert_eq!(res, Some(62));
+    }
+
+    #[test]
+    fn test_contiguous_ranges_all_solutions() {
+        let res = contiguous_ranges(&[1, 2, 3, 0, 3, 3], 6);
+        assert_eq!(res, vec![0..3, 0..4, 2..5, 3..6, 4..6]);
+    }
+
+    #[test]
+    fn test_contiguous_ranges_negative() {
+        let res = contiguous_ranges(&[5, -2, 4, -3, 1], 2);
+        assert_eq!(res, vec![1..3, 2..5]);
+    }
+
+    #[test]
+    fn test_contiguous_ranges_none() {
+        assert!(contiguous_ranges(&[5, 1, 7], 5).is_empty());
+        assert!(contiguous_ranges(&[], 0).is_empty());
+        assert_eq!(find_encryption_weakness(&[1, 2, 4, 100], 2), None);
+        assert_eq!(find_encryption_weakness(&[1, 2, 3, 5, 8], 2), None);
+        assert_eq!(find_encryption_weakness(&[1, 2, u64::MAX], 2), None);
+    }
+
+    #[test]
+    fn test_contiguous_ranges_large_values() {
+        assert_eq!(contiguous_ranges(&[i64::MAX, 1, -1], i64::MAX), vec![0..3]);
+        assert_eq!(contiguous_ranges(&[u64::MAX, 1, 2], 3), vec![1..3]);
+        assert_eq!(
+            find_encryption_weakness(&[4, 6, u64::MAX, 10, 16, 10], 3),
+            Some(10)
+        );
     }
 }
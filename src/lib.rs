@@ -1,6 +1,9 @@
 #[macro_use]
 extern crate aoc_runner_derive;
 
+pub mod assembler;
+pub mod automaton;
+pub mod console;
 pub mod day1;
 pub mod day10;
 pub mod day11;
@@ -21,5 +24,8 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod masked_memory;
+pub mod number_theory;
+pub mod trace;
 
 aoc_lib! { year = 2020 }
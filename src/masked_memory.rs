@@ -0,0 +1,720 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MaskError {
+    Empty,
+    TooWide(usize),
+    InvalidCharacter { index: usize, found: char },
+    InvalidWidth(u32),
+    WidthMismatch { expected: u32, found: u32 },
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MaskError::Empty => write!(f, "mask is empty"),
+            MaskError::TooWide(width) => {
+                write!(f, "mask is {} bits wide, at most 64 are supported", width)
+            }
+            MaskError::InvalidCharacter { index, found } => {
+                write!(f, "invalid character {:?} at position {}", found, index)
+            }
+            MaskError::InvalidWidth(width) => {
+                write!(
+                    f,
+                    "memory width must be between 1 and 64 bits, got {}",
+                    width
+                )
+            }
+            MaskError::WidthMismatch { expected, found } => write!(
+                f,
+                "mask is {} bits wide but memory is {} bits wide",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MaskError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mask {
+    pub ones: u64,
+    pub zeros: u64,
+    pub floating: u64,
+    pub width: u32,
+}
+
+impl Mask {
+    pub fn zeros(width: u32) -> Self {
+        let mut mask = Mask {
+            ones: 0,
+            zeros: 0,
+            floating: 0,
+            width,
+        };
+        mask.zeros = mask.width_mask();
+        mask
+    }
+
+    pub fn floating(width: u32) -> Self {
+        let mut mask = Mask {
+            ones: 0,
+            zeros: 0,
+            floating: 0,
+            width,
+        };
+        mask.floating = mask.width_mask();
+        mask
+    }
+
+    pub fn width_mask(&self) -> u64 {
+        match self.width {
+            64 => u64::MAX,
+            width => (1 << width) - 1,
+        }
+    }
+
+    pub fn apply_value(&self, value: u64) -> u64 {
+        (value | self.ones) & !self.zeros & self.width_mask()
+    }
+
+    pub fn address_pattern(&self, address: u64) -> AddressPattern {
+        AddressPattern {
+            bits: (address | self.ones) & !self.floating & self.width_mask(),
+            floating: self.floating & self.width_mask(),
+        }
+    }
+
+    pub fn apply_address(&self, address: u64) -> impl Iterator<Item = u64> {
+        self.address_pattern(address).addresses()
+    }
+}
+
+// A set of addresses sharing the fixed `bits`, with every bit in `floating` taking both values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressPattern {
+    pub bits: u64,
+    pub floating: u64,
+}
+
+impl AddressPattern {
+    pub fn exact(address: u64) -> Self {
+        AddressPattern {
+            bits: address,
+            floating: 0,
+        }
+    }
+
+    pub fn count(&self) -> u128 {
+        1 << self.floating.count_ones()
+    }
+
+    pub fn contains(&self, address: u64) -> bool {
+        address & !self.floating == self.bits
+    }
+
+    pub fn overlaps(&self, other: &AddressPattern) -> bool {
+        (self.bits ^ other.bits) & !self.floating & !other.floating == 0
+    }
+
+    pub fn intersect(&self, other: &AddressPattern) -> Option<AddressPattern> {
+        Some(AddressPattern {
+            bits: self.bits | other.bits,
+            floating: self.floating & other.floating,
+        })
+        .filter(|_| self.overlaps(other))
+    }
+
+    // Splits the addresses of `self` not covered by `other` into disjoint patterns.
+    pub fn subtract(&self, other: &AddressPattern) -> Vec<AddressPattern> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+
+        let mut rest = *self;
+        let mut pieces = Vec::new();
+        let mut split = self.floating & !other.floating;
+        while split != 0 {
+            let bit = split & split.wrapping_neg();
+            split &= split - 1;
+            rest.floating &= !bit;
+            pieces.push(AddressPattern {
+                bits: rest.bits | (!other.bits & bit),
+                floating: rest.floating,
+            });
+            rest.bits |= other.bits & bit;
+        }
+
+        pieces
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = u64> {
+        let base = self.bits;
+        let floating = self.floating;
+        let mut subset = Some(0u64);
+        std::iter::from_fn(move || {
+            let current = subset?;
+            subset = match current.wrapping_sub(floating) & floating {
+                0 => None,
+                next => Some(next),
+            };
+            Some(base | current)
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct PatternStore {
+    patterns: Vec<(AddressPattern, u64)>,
+}
+
+impl PatternStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&mut self, pattern: AddressPattern, value: u64) {
+        let mut patterns = Vec::with_capacity(self.patterns.len() + 1);
+        for (existing, v) in self.patterns.drain(..) {
+            patterns.extend(existing.subtract(&pattern).into_iter().map(|p| (p, v)));
+        }
+        patterns.push((pattern, value));
+        self.patterns = patterns;
+    }
+
+    pub fn get(&self, address: u64) -> Option<u64> {
+        self.patterns
+            .iter()
+            .find(|(p, _)| p.contains(address))
+            .map(|(_, v)| *v)
+    }
+
+    pub fn read(&self, address: u64) -> u64 {
+        self.get(address).unwrap_or(0)
+    }
+
+    pub fn patterns(&self) -> &[(AddressPattern, u64)] {
+        &self.patterns
+    }
+
+    pub fn sum(&self) -> u128 {
+        self.patterns
+            .iter()
+            .map(|(p, v)| p.count() * *v as u128)
+            .sum()
+    }
+}
+
+impl FromStr for Mask {
+    type Err = MaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.len() {
+            0 => return Err(MaskError::Empty),
+            width if width > 64 => return Err(MaskError::TooWide(width)),
+            _ => {}
+        }
+
+        let mut mask = Mask {
+            ones: 0,
+            zeros: 0,
+            floating: 0,
+            width: s.len() as u32,
+        };
+        for (index, c) in s.chars().enumerate() {
+            let bit = 1 << (s.len() - 1 - index);
+            match c {
+                '1' => mask.ones |= bit,
+                '0' => mask.zeros |= bit,
+                'X' => mask.floating |= bit,
+                found => return Err(MaskError::InvalidCharacter { index, found }),
+            }
+        }
+
+        Ok(mask)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decoder {
+    V1,
+    V2,
+}
+
+impl Decoder {
+    pub fn identity_mask(&self, width: u32) -> Mask {
+        match self {
+            Decoder::V1 => Mask::floating(width),
+            Decoder::V2 => Mask::zeros(width),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteRecord {
+    pub address: u64,
+    pub value: u64,
+    pub touched: AddressPattern,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MemoryDiff {
+    pub pattern: AddressPattern,
+    pub left: Option<u64>,
+    pub right: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DumpError {
+    TooManyAddresses { count: u128, limit: usize },
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DumpError::TooManyAddresses { count, limit } => write!(
+                f,
+                "memory holds {} addresses, more than the limit of {}",
+                count, limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DumpError {}
+
+fn sort_entries(entries: &mut [(AddressPattern, u64)]) {
+    entries.sort_unstable_by_key(|(p, _)| (p.bits, p.floating));
+}
+
+// Returns the parts of `pattern` that none of the `others` cover.
+fn uncovered(pattern: AddressPattern, others: &[(AddressPattern, u64)]) -> Vec<AddressPattern> {
+    others.iter().fold(vec![pattern], |pieces, (other, _)| {
+        pieces.iter().flat_map(|p| p.subtract(other)).collect()
+    })
+}
+
+// Version 1 only ever writes single addresses, so it keeps a plain map. Version 2 needs the
+// pattern store to avoid expanding floating bits.
+enum Storage {
+    Exact(HashMap<u64, u64>),
+    Patterns(PatternStore),
+}
+
+impl Storage {
+    fn get(&self, address: u64) -> Option<u64> {
+        match self {
+            Storage::Exact(memory) => memory.get(&address).copied(),
+            Storage::Patterns(store) => store.get(address),
+        }
+    }
+
+    fn entries(&self) -> Vec<(AddressPattern, u64)> {
+        match self {
+            Storage::Exact(memory) => memory
+                .iter()
+                .map(|(a, v)| (AddressPattern::exact(*a), *v))
+                .collect(),
+            Storage::Patterns(store) => store.patterns().to_vec(),
+        }
+    }
+
+    fn sum(&self) -> u128 {
+        match self {
+            Storage::Exact(memory) => memory.values().map(|v| *v as u128).sum(),
+            Storage::Patterns(store) => store.sum(),
+        }
+    }
+}
+
+pub struct MaskedMemory {
+    decoder: Decoder,
+    mask: Mask,
+    memory: Storage,
+    log: Vec<WriteRecord>,
+}
+
+impl MaskedMemory {
+    pub fn new(decoder: Decoder, width: u32) -> Result<Self, MaskError> {
+        if !(1..=64).contains(&width) {
+            return Err(MaskError::InvalidWidth(width));
+        }
+        Ok(MaskedMemory {
+            decoder,
+            mask: decoder.identity_mask(width),
+            memory: match decoder {
+                Decoder::V1 => Storage::Exact(HashMap::new()),
+                Decoder::V2 => Storage::Patterns(PatternStore::new()),
+            },
+            log: Vec::new(),
+        })
+    }
+
+    pub fn decoder(&self) -> Decoder {
+        self.decoder
+    }
+
+    pub fn mask(&self) -> &Mask {
+        &self.mask
+    }
+
+    pub fn set_mask(&mut self, mask: Mask) -> Result<(), MaskError> {
+        if mask.width != self.mask.width {
+            return Err(MaskError::WidthMismatch {
+                expected: self.mask.width,
+                found: mask.width,
+            });
+        }
+        self.mask = mask;
+        Ok(())
+    }
+
+    pub fn write(&mut self, address: u64, value: u64) {
+        let (touched, stored) = match &mut self.memory {
+            Storage::Exact(memory) => {
+                let stored = self.mask.apply_value(value);
+                memory.insert(address, stored);
+                (AddressPattern::exact(address), stored)
+            }
+            Storage::Patterns(store) => {
+                let touched = self.mask.address_pattern(address);
+                store.write(touched, value);
+                (touched, value)
+            }
+        };
+        self.log.push(WriteRecord {
+            address,
+            value: stored,
+            touched,
+        });
+    }
+
+    pub fn read(&self, address: u64) -> u64 {
+        self.get(address).unwrap_or(0)
+    }
+
+    pub fn get(&self, address: u64) -> Option<u64> {
+        self.memory.get(address)
+    }
+
+    pub fn log(&self) -> &[WriteRecord] {
+        &self.log
+    }
+
+    pub fn dump(&self) -> Vec<(AddressPattern, u64)> {
+        let mut dump = self.memory.entries();
+        sort_entries(&mut dump);
+        dump
+    }
+
+    pub fn dump_addresses(&self, limit: usize) -> Result<Vec<(u64, u64)>, DumpError> {
+        let dump = self.dump();
+        let count: u128 = dump.iter().map(|(p, _)| p.count()).sum();
+        if count > limit as u128 {
+            return Err(DumpError::TooManyAddresses { count, limit });
+        }
+
+        let mut addresses: Vec<(u64, u64)> = dump
+            .into_iter()
+            .flat_map(|(p, v)| p.addresses().map(move |a| (a, v)))
+            .collect();
+        addresses.sort_unstable();
+        Ok(addresses)
+    }
+
+    pub fn diff(&self, other: &MaskedMemory) -> Vec<MemoryDiff> {
+        let left = self.dump();
+        let right = other.dump();
+        let mut diff = Vec::new();
+
+        for (lp, lv) in left.iter() {
+            for (rp, rv) in right.iter() {
+                if let Some(pattern) = lp.intersect(rp).filter(|_| lv != rv) {
+                    diff.push(MemoryDiff {
+                        pattern,
+                        left: Some(*lv),
+                        right: Some(*rv),
+                    });
+                }
+            }
+            diff.extend(
+                uncovered(*lp, &right)
+                    .into_iter()
+                    .map(|pattern| MemoryDiff {
+                        pattern,
+                        left: Some(*lv),
+                        right: None,
+                    }),
+            );
+        }
+        for (rp, rv) in right.iter() {
+            diff.extend(uncovered(*rp, &left).into_iter().map(|pattern| MemoryDiff {
+                pattern,
+                left: None,
+                right: Some(*rv),
+            }));
+        }
+
+        diff.sort_unstable_by_key(|d| (d.pattern.bits, d.pattern.floating));
+        diff
+    }
+
+    pub fn sum(&self) -> u128 {
+        self.memory.sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AddressPattern, Decoder, DumpError, Mask, MaskError, MaskedMemory, MemoryDiff};
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_mask() {
+        let mask: Mask = "X1001X".parse().unwrap();
+        assert_eq!(mask.ones, 0b010010);
+        assert_eq!(mask.zeros, 0b001100);
+        assert_eq!(mask.floating, 0b100001);
+        assert_eq!(mask.width, 6);
+        assert_eq!(mask.width_mask(), 0b111111);
+
+        let wide: Mask = "X".repeat(64).parse().unwrap();
+        assert_eq!(wide.floating, u64::MAX);
+    }
+
+    #[test]
+    fn test_parse_mask_errors() {
+        assert_eq!("".parse::<Mask>(), Err(MaskError::Empty));
+        assert_eq!("0".repeat(65).parse::<Mask>(), Err(MaskError::TooWide(65)));
+        assert_eq!(
+            "10x1".parse::<Mask>(),
+            Err(MaskError::InvalidCharacter {
+                index: 2,
+                found: 'x'
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_address() {
+        let mask: Mask = "X1001X".parse().unwrap();
+        let mut addresses: Vec<u64> = mask.apply_address(42).collect();
+        addresses.sort_unstable();
+        assert_eq!(addresses, vec![26, 27, 58, 59]);
+
+        let fixed: Mask = "0000".parse().unwrap();
+        assert_eq!(
+            fixed.apply_address(0b1010).collect::<Vec<_>>(),
+            vec![0b1010]
+        );
+    }
+
+    #[test]
+    fn test_decoders() {
+        let mut memory = MaskedMemory::new(Decoder::V1, 8).unwrap();
+        assert_eq!(memory.decoder(), Decoder::V1);
+        memory.write(1, 0x1ff);
+        assert_eq!(memory.read(1), 0xff);
+        memory.set_mask("XXXXXX1X".parse().unwrap()).unwrap();
+        memory.write(2, 0);
+        assert_eq!(memory.read(2), 2);
+
+        let mut memory = MaskedMemory::new(Decoder::V2, 4).unwrap();
+        memory.write(3, 1);
+        assert_eq!(memory.sum(), 1);
+        memory.set_mask("00XX".parse().unwrap()).unwrap();
+        memory.write(0b1000, 5);
+        assert_eq!(memory.sum(), 21);
+        assert_eq!(memory.read(0b1011), 5);
+    }
+
+    #[test]
+    fn test_width_validation() {
+        assert!(MaskedMemory::new(Decoder::V1, 64).is_ok());
+        assert_eq!(
+            MaskedMemory::new(Decoder::V1, 65).err(),
+            Some(MaskError::InvalidWidth(65))
+        );
+        assert_eq!(
+            MaskedMemory::new(Decoder::V2, 0).err(),
+            Some(MaskError::InvalidWidth(0))
+        );
+
+        let mut memory = MaskedMemory::new(Decoder::V1, 8).unwrap();
+        assert_eq!(
+            memory.set_mask("XXXX".parse().unwrap()),
+            Err(MaskError::WidthMismatch {
+                expected: 8,
+                found: 4
+            })
+        );
+        assert_eq!(memory.mask().width, 8);
+    }
+
+    #[test]
+    fn test_subtract_patterns() {
+        let a: Mask = "XXX".parse().unwrap();
+        let b: Mask = "1X0".parse().unwrap();
+        let pieces = a.address_pattern(0).subtract(&b.address_pattern(0));
+        let mut addresses: Vec<u64> = pieces.iter().flat_map(|p| p.addresses()).collect();
+        addresses.sort_unstable();
+        assert_eq!(addresses, vec![0, 1, 2, 3, 5, 7]);
+        assert_eq!(pieces.iter().map(|p| p.count()).sum::<u128>(), 6);
+
+        let exact = AddressPattern::exact(0b100);
+        assert!(exact.subtract(&b.address_pattern(0)).is_empty());
+        assert_eq!(
+            exact.subtract(&AddressPattern::exact(1)),
+            vec![AddressPattern::exact(0b100)]
+        );
+    }
+
+    #[test]
+    fn test_many_floating_bits() {
+        let mut memory = MaskedMemory::new(Decoder::V2, 64).unwrap();
+        memory
+            .set_mask(format!("{}1", "X".repeat(63)).parse().unwrap())
+            .unwrap();
+        memory.write(0, 3);
+        memory.set_mask("0".repeat(64).parse().unwrap()).unwrap();
+        memory.write(1, 1);
+        assert_eq!(memory.sum(), 3 * (1u128 << 63) - 2);
+        assert_eq!(memory.read(u64::MAX), 3);
+        assert_eq!(memory.read(2), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_pattern_store_matches_brute_force(
+            writes in prop::collection::vec(("[01X]{6}", 0u64..64, 0u64..100), 1..12)
+        ) {
+            let mut memory = MaskedMemory::new(Decoder::V2, 6).unwrap();
+            let mut brute = HashMap::new();
+            for (mask, address, value) in writes {
+                let mask: Mask = mask.parse().unwrap();
+                memory.set_mask(mask).unwrap();
+                memory.write(address, value);
+                for a in mask.apply_address(address) {
+                    brute.insert(a, value);
+                }
+            }
+            prop_assert_eq!(memory.sum(), brute.values().map(|v| *v as u128).sum::<u128>());
+            for a in 0..64 {
+                prop_assert_eq!(memory.read(a), brute.get(&a).copied().unwrap_or(0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_dump_and_log() {
+        let mut memory = MaskedMemory::new(Decoder::V2, 6).unwrap();
+        memory.set_mask("X1001X".parse().unwrap()).unwrap();
+        memory.write(42, 100);
+        memory.set_mask("00X0XX".parse().unwrap()).unwrap();
+        memory.write(26, 1);
+
+        assert_eq!(
+            memory.dump(),
+            vec![
+                (
+                    AddressPattern {
+                        bits: 16,
+                        floating: 0b001011
+                    },
+                    1
+                ),
+                (
+                    AddressPattern {
+                        bits: 58,
+                        floating: 0b000001
+                    },
+                    100
+                ),
+            ]
+        );
+        let addresses = memory.dump_addresses(10).unwrap();
+        assert_eq!(addresses.len(), 10);
+        assert!(addresses.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(addresses[0], (16, 1));
+        assert!(addresses.contains(&(58, 100)));
+        assert_eq!(
+            memory.dump_addresses(9),
+            Err(DumpError::TooManyAddresses {
+                count: 10,
+                limit: 9
+            })
+        );
+
+        let log = memory.log();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].touched.addresses().count(), 4);
+        assert_eq!(log[1].touched.count(), 8);
+        assert!(log[1].touched.contains(27));
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut v1 = MaskedMemory::new(Decoder::V1, 4).unwrap();
+        let mut v2 = MaskedMemory::new(Decoder::V2, 4).unwrap();
+        for memory in [&mut v1, &mut v2].iter_mut() {
+            memory.set_mask("X00X".parse().unwrap()).unwrap();
+            memory.write(2, 7);
+            memory.write(5, 0);
+        }
+
+        assert_eq!(v1.dump_addresses(2), Ok(vec![(2, 1), (5, 0)]));
+        let diff = |bits, floating, left, right| MemoryDiff {
+            pattern: AddressPattern { bits, floating },
+            left,
+            right,
+        };
+        assert_eq!(
+            v1.diff(&v2),
+            vec![
+                diff(2, 0, Some(1), Some(7)),
+                diff(3, 0b1000, None, Some(7)),
+                diff(4, 0b1000, None, Some(0)),
+                diff(10, 0, None, Some(7)),
+                diff(13, 0, None, Some(0)),
+            ]
+        );
+        assert!(v1.diff(&v1).is_empty());
+        assert!(v2.diff(&v2).is_empty());
+    }
+
+    #[test]
+    fn test_dump_and_diff_wide_floating() {
+        let mut memory = MaskedMemory::new(Decoder::V2, 64).unwrap();
+        memory
+            .set_mask(format!("{}1", "X".repeat(63)).parse().unwrap())
+            .unwrap();
+        memory.write(0, 3);
+        memory.set_mask("0".repeat(64).parse().unwrap()).unwrap();
+        memory.write(1, 1);
+
+        let dump = memory.dump();
+        assert_eq!(dump.len(), 64);
+        assert_eq!(
+            dump.iter().map(|(p, _)| p.count()).sum::<u128>(),
+            1u128 << 63
+        );
+        assert_eq!(
+            memory.dump_addresses(1000),
+            Err(DumpError::TooManyAddresses {
+                count: 1 << 63,
+                limit: 1000
+            })
+        );
+
+        let empty = MaskedMemory::new(Decoder::V2, 64).unwrap();
+        let diff = memory.diff(&empty);
+        assert_eq!(diff.len(), dump.len());
+        assert!(diff.iter().all(|d| d.right.is_none()));
+        assert!(memory.diff(&memory).is_empty());
+    }
+}
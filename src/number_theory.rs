@@ -0,0 +1,338 @@
+use num_bigint::BigInt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Bezout<T> {
+    pub gcd: T,
+    pub s: T,
+    pub t: T,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Congruence<T> {
+    pub residue: T,
+    pub modulus: T,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CrtError {
+    InvalidModulus(usize),
+    Inconsistent(usize),
+    Overflow,
+}
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    extended_gcd(a, b).gcd
+}
+
+pub fn lcm(a: i128, b: i128) -> Option<i128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b).map(i128::abs)
+}
+
+// Returns gcd(a, b) >= 0 together with s and t such that a * s + b * t == gcd(a, b).
+pub fn extended_gcd(a: i128, b: i128) -> Bezout<i128> {
+    let (mut r0, mut r1) = (a, b);
+    let (mut s0, mut s1) = (1i128, 0i128);
+    let (mut t0, mut t1) = (0i128, 1i128);
+
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
+        (t0, t1) = (t1, t0 - q * t1);
+    }
+
+    if r0 < 0 {
+        Bezout {
+            gcd: -r0,
+            s: -s0,
+            t: -t0,
+        }
+    } else {
+        Bezout {
+            gcd: r0,
+            s: s0,
+            t: t0,
+        }
+    }
+}
+
+pub fn mod_inverse(a: i128, modulus: i128) -> Option<i128> {
+    if modulus <= 0 {
+        return None;
+    }
+    let bezout = extended_gcd(a.rem_euclid(modulus), modulus);
+    Some(bezout.s.rem_euclid(modulus)).filter(|_| bezout.gcd == 1)
+}
+
+fn mul_mod(a: i128, b: i128, modulus: i128) -> Option<i128> {
+    a.checked_mul(b).map(|p| p.rem_euclid(modulus))
+}
+
+pub fn crt(congruences: &[(i128, i128)]) -> Result<Congruence<i128>, CrtError> {
+    let mut result = Congruence {
+        residue: 0i128,
+        modulus: 1i128,
+    };
+
+    for (i, (residue, modulus)) in congruences.iter().enumerate() {
+        if *modulus <= 0 {
+            return Err(CrtError::InvalidModulus(i));
+        }
+        let residue = residue.rem_euclid(*modulus);
+        let bezout = extended_gcd(result.modulus, *modulus);
+        let difference = residue - result.residue;
+        if difference % bezout.gcd != 0 {
+            return Err(CrtError::Inconsistent(i));
+        }
+
+        let step = modulus / bezout.gcd;
+        let combined = (result.modulus / bezout.gcd)
+            .checked_mul(*modulus)
+            .ok_or(CrtError::Overflow)?;
+        let k = mul_mod(difference / bezout.gcd, bezout.s, step).ok_or(CrtError::Overflow)?;
+        let residue = result
+            .modulus
+            .checked_mul(k)
+            .and_then(|v| v.checked_add(result.residue))
+            .ok_or(CrtError::Overflow)?
+            .rem_euclid(combined);
+
+        result = Congruence {
+            residue,
+            modulus: combined,
+        };
+    }
+
+    Ok(result)
+}
+
+fn rem_euclid_big(a: &BigInt, modulus: &BigInt) -> BigInt {
+    let r = a % modulus;
+    if r < BigInt::from(0) {
+        r + modulus
+    } else {
+        r
+    }
+}
+
+pub fn extended_gcd_big(a: &BigInt, b: &BigInt) -> Bezout<BigInt> {
+    let (mut r0, mut r1) = (a.clone(), b.clone());
+    let (mut s0, mut s1) = (BigInt::from(1), BigInt::from(0));
+    let (mut t0, mut t1) = (BigInt::from(0), BigInt::from(1));
+
+    while r1 != BigInt::from(0) {
+        let q = &r0 / &r1;
+        let r2 = &r0 - &q * &r1;
+        let s2 = &s0 - &q * &s1;
+        let t2 = &t0 - &q * &t1;
+        r0 = std::mem::replace(&mut r1, r2);
+        s0 = std::mem::replace(&mut s1, s2);
+        t0 = std::mem::replace(&mut t1, t2);
+    }
+
+    if r0 < BigInt::from(0) {
+        Bezout {
+            gcd: -r0,
+            s: -s0,
+            t: -t0,
+        }
+    } else {
+        Bezout {
+            gcd: r0,
+            s: s0,
+            t: t0,
+        }
+    }
+}
+
+pub fn crt_big(congruences: &[(BigInt, BigInt)]) -> Result<Congruence<BigInt>, CrtError> {
+    let mut result = Congruence {
+        residue: BigInt::from(0),
+        modulus: BigInt::from(1),
+    };
+
+    for (i, (residue, modulus)) in congruences.iter().enumerate() {
+        if *modulus <= BigInt::from(0) {
+            return Err(CrtError::InvalidModulus(i));
+        }
+        let residue = rem_euclid_big(residue, modulus);
+        let bezout = extended_gcd_big(&result.modulus, modulus);
+        let difference = residue - &result.residue;
+        if &difference % &bezout.gcd != BigInt::from(0) {
+            return Err(CrtError::Inconsistent(i));
+        }
+
+        let step = modulus / &bezout.gcd;
+        let combined = &result.modulus / &bezout.gcd * modulus;
+        let k = rem_euclid_big(&(difference / &bezout.gcd * &bezout.s), &step);
+        let residue = rem_euclid_big(&(&result.modulus * k + &result.residue), &combined);
+
+        result = Congruence {
+            residue,
+            modulus: combined,
+        };
+    }
+
+    Ok(result)
+}
+
+// Solves with i128 arithmetic and only switches to BigInt when an intermediate value overflows.
+pub fn crt_with_fallback(congruences: &[(i128, i128)]) -> Result<Congruence<BigInt>, CrtError> {
+    match crt(congruences) {
+        Ok(res) => Ok(Congruence {
+            residue: BigInt::from(res.residue),
+            modulus: BigInt::from(res.modulus),
+        }),
+        Err(CrtError::Overflow) => {
+            let big: Vec<(BigInt, BigInt)> = congruences
+                .iter()
+                .map(|(a, n)| (BigInt::from(*a), BigInt::from(*n)))
+                .collect();
+            crt_big(&big)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        crt, crt_big, crt_with_fallback, extended_gcd, gcd, lcm, mod_inverse, Congruence, CrtError,
+    };
+    use num_bigint::BigInt;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(-12, 18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(i128::MAX, i128::MAX - 1), None);
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let res = extended_gcd(240, 46);
+        assert_eq!(res.gcd, 2);
+        assert_eq!(240 * res.s + 46 * res.t, 2);
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(6, 9), None);
+    }
+
+    fn solve(n: &[i128], a: &[i128]) -> i128 {
+        let congruences: Vec<(i128, i128)> = a.iter().copied().zip(n.iter().copied()).collect();
+        crt(&congruences).unwrap().residue
+    }
+
+    #[test]
+    fn test_theorem() {
+        assert_eq!(solve(&[3, 5, 7], &[2, 3, 2]), 23);
+    }
+
+    #[test]
+    fn test_theorem_2() {
+        assert_eq!(solve(&[5, 7, 12], &[0, 6, 10]), 370);
+    }
+
+    #[test]
+    fn test_theorem_3() {
+        assert_eq!(solve(&[13, 5, 7], &[2, 3, 2]), 93);
+    }
+
+    #[test]
+    fn test_theorem_4() {
+        assert_eq!(solve(&[13, 5, 29], &[2, 5, 7]), 210);
+    }
+
+    #[test]
+    fn test_theorem_5() {
+        assert_eq!(solve(&[13, 5, 29], &[24, 5, 7]), 1805);
+    }
+
+    #[test]
+    fn test_crt_non_coprime() {
+        assert_eq!(
+            crt(&[(2, 6), (8, 10)]),
+            Ok(Congruence {
+                residue: 8,
+                modulus: 30
+            })
+        );
+        assert_eq!(crt(&[(1, 6), (2, 4)]), Err(CrtError::Inconsistent(1)));
+        assert_eq!(crt(&[(1, 0)]), Err(CrtError::InvalidModulus(0)));
+    }
+
+    #[test]
+    fn test_crt_overflow_falls_back_to_big() {
+        let primes: [i128; 3] = [
+            1_000_000_000_000_000_003,
+            1_000_000_000_000_000_009,
+            1_000_000_000_000_000_031,
+        ];
+        let congruences: Vec<(i128, i128)> = primes.iter().map(|p| (p - 1, *p)).collect();
+        assert_eq!(crt(&congruences), Err(CrtError::Overflow));
+
+        let res = crt_with_fallback(&congruences).unwrap();
+        let product: BigInt = primes.iter().map(|p| BigInt::from(*p)).product();
+        assert_eq!(res.modulus, product);
+        assert_eq!(res.residue, product - 1);
+
+        assert_eq!(
+            crt_with_fallback(&[(2, 3), (3, 5)]),
+            Ok(Congruence {
+                residue: BigInt::from(8),
+                modulus: BigInt::from(15)
+            })
+        );
+        assert_eq!(
+            crt_with_fallback(&[(1, 6), (2, 4)]),
+            Err(CrtError::Inconsistent(1))
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_crt_matches_brute_force(
+            congruences in prop::collection::vec((0i128..40, 1i128..25), 1..4)
+        ) {
+            let modulus = congruences
+                .iter()
+                .fold(1, |acc, (_, n)| lcm(acc, *n).unwrap());
+            let brute = (0..modulus).find(|x| congruences.iter().all(|(a, n)| x % n == a % n));
+            match crt(&congruences) {
+                Ok(res) => {
+                    prop_assert_eq!(Some(res.residue), brute);
+                    prop_assert_eq!(res.modulus, modulus);
+                }
+                Err(e) => {
+                    prop_assert!(matches!(e, CrtError::Inconsistent(_)));
+                    prop_assert_eq!(brute, None);
+                }
+            }
+
+            let big: Vec<(BigInt, BigInt)> = congruences
+                .iter()
+                .map(|(a, n)| (BigInt::from(*a), BigInt::from(*n)))
+                .collect();
+            prop_assert_eq!(
+                crt_big(&big).ok().map(|c| c.residue),
+                brute.map(BigInt::from)
+            );
+        }
+
+        #[test]
+        fn prop_mod_inverse_matches_brute_force(a in -50i128..50, m in 1i128..40) {
+            let brute = (0..m).find(|x| (a * x).rem_euclid(m) == 1 % m);
+            prop_assert_eq!(mod_inverse(a, m), brute);
+        }
+    }
+}
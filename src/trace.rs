@@ -0,0 +1,193 @@
+use crate::console::{Console, ExitReason, Instruction, OpCode};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: usize,
+    pub acc: i64,
+}
+
+#[derive(Debug)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+    pub exit: ExitReason,
+    pub loop_entry: Option<usize>,
+}
+
+impl Trace {
+    pub fn loop_body(&self) -> &[TraceEntry] {
+        self.loop_entry
+            .and_then(|entry| self.entries.iter().position(|e| e.pc == entry))
+            .map(|start| &self.entries[start..])
+            .unwrap_or(&[])
+    }
+}
+
+pub fn trace<I: Instruction>(program: &[I]) -> Trace {
+    let mut console = Console::new(program);
+    let mut entries = Vec::new();
+
+    let exit = loop {
+        let pc = console.state().pc;
+        let steps = console.state().steps;
+        let exit = console.step();
+        if console.state().steps > steps {
+            entries.push(TraceEntry {
+                pc,
+                acc: console.state().acc,
+            });
+        }
+        if let Some(reason) = exit {
+            break reason;
+        }
+    };
+
+    let loop_entry = match exit {
+        ExitReason::InfiniteLoop => Some(console.state().pc),
+        _ => None,
+    };
+
+    Trace {
+        entries,
+        exit,
+        loop_entry,
+    }
+}
+
+fn block_leaders(program: &[OpCode]) -> BTreeSet<usize> {
+    let mut leaders = BTreeSet::new();
+    leaders.insert(0);
+    for (pc, op_code) in program.iter().enumerate() {
+        if let OpCode::Jmp(_) = op_code {
+            let target = op_code.successor(pc);
+            if (0..program.len() as i64).contains(&target) {
+                leaders.insert(target as usize);
+            }
+            leaders.insert(pc + 1);
+        }
+    }
+    leaders.retain(|l| *l < program.len());
+    leaders
+}
+
+fn node_name(target: i64, len: usize) -> String {
+    match target {
+        _ if target == len as i64 => "exit".to_owned(),
+        _ if target < 0 || target > len as i64 => format!("oob{}", target).replace('-', "_"),
+        _ => format!("b{}", target),
+    }
+}
+
+pub fn cfg_to_dot(program: &[OpCode]) -> String {
+    let leaders: Vec<usize> = block_leaders(program).into_iter().collect();
+    let mut dot = String::from("digraph program {\n    node [shape=box, fontname=monospace];\n");
+    let mut targets = BTreeSet::new();
+
+    for (i, start) in leaders.iter().enumerate() {
+        let end = leaders.get(i + 1).copied().unwrap_or(program.len());
+        let label: String = (*start..end)
+            .map(|pc| format!("{}: {}\\l", pc, program[pc]))
+            .collect();
+        writeln!(dot, "    b{} [label=\"{}\"];", start, label).unwrap();
+
+        let last = end - 1;
+        let target = program[last].successor(last);
+        let kind = match program[last] {
+            OpCode::Jmp(_) => "jmp",
+            _ => "next",
+        };
+        writeln!(
+            dot,
+            "    b{} -> {} [label=\"{}\"];",
+            start,
+            node_name(target, program.len()),
+            kind
+        )
+        .unwrap();
+        if !(0..program.len() as i64).contains(&target) {
+            targets.insert(target);
+        }
+    }
+
+    for target in targets {
+        let label = match target {
+            _ if target == program.len() as i64 => "exit".to_owned(),
+            _ => format!("out of bounds ({})", target),
+        };
+        writeln!(
+            dot,
+            "    {} [shape=oval, label=\"{}\"];",
+            node_name(target, program.len()),
+            label
+        )
+        .unwrap();
+    }
+    dot.push_str("}\n");
+
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cfg_to_dot, trace, TraceEntry};
+    use crate::assembler::assemble;
+    use crate::console::{ExitReason, OpCode};
+
+    const GIVEN: &str = "nop +0
+        acc +1
+        jmp +4
+        acc +3
+        jmp -3
+        acc -99
+        acc +1
+        jmp -4
+        acc +6";
+
+    #[test]
+    fn test_trace_given() {
+        let program = assemble(GIVEN).unwrap();
+        let res = trace(&program);
+        assert_eq!(res.exit, ExitReason::InfiniteLoop);
+        assert_eq!(res.loop_entry, Some(1));
+        let pcs: Vec<usize> = res.entries.iter().map(|e| e.pc).collect();
+        assert_eq!(pcs, vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(res.entries.last(), Some(&TraceEntry { pc: 4, acc: 5 }));
+        assert_eq!(res.loop_body().len(), 6);
+        assert_eq!(res.loop_body()[0], TraceEntry { pc: 1, acc: 1 });
+    }
+
+    #[test]
+    fn test_trace_terminates() {
+        let program = assemble("acc +2\njmp +2\nacc +5\nacc -1").unwrap();
+        let res = trace(&program);
+        assert_eq!(res.exit, ExitReason::Terminated);
+        assert_eq!(res.loop_entry, None);
+        assert!(res.loop_body().is_empty());
+        assert_eq!(res.entries.last(), Some(&TraceEntry { pc: 3, acc: 1 }));
+    }
+
+    #[test]
+    fn test_trace_out_of_bounds() {
+        let res = trace(&[OpCode::Acc(5), OpCode::Jmp(7)]);
+        assert_eq!(res.exit, ExitReason::OutOfBounds(8));
+        assert_eq!(
+            res.entries,
+            vec![TraceEntry { pc: 0, acc: 5 }, TraceEntry { pc: 1, acc: 5 }]
+        );
+    }
+
+    #[test]
+    fn test_cfg_to_dot() {
+        let program = assemble(GIVEN).unwrap();
+        let dot = cfg_to_dot(&program);
+        assert!(dot.contains("b0 [label=\"0: nop +0\\l\"];"));
+        assert!(dot.contains("b0 -> b1 [label=\"next\"];"));
+        assert!(dot.contains("b1 [label=\"1: acc +1\\l2: jmp +4\\l\"];"));
+        assert!(dot.contains("b1 -> b6 [label=\"jmp\"];"));
+        assert!(dot.contains("b3 -> b1 [label=\"jmp\"];"));
+        assert!(dot.contains("b5 -> b6 [label=\"next\"];"));
+        assert!(dot.contains("b8 -> exit [label=\"next\"];"));
+        assert!(dot.contains("exit [shape=oval, label=\"exit\"];"));
+    }
+}